@@ -1,20 +1,24 @@
 #![allow(dead_code)]
 
 use crate::token;
-use crate::token::{Token, TokenType};
+use crate::token::{Segment, Token};
 
 const ZERO_CHAR: char = 0 as char;
 
-pub struct Lexer {
-    input: Vec<char>,
+/// Lexer over borrowed source text (e.g. a memory-mapped file);
+/// positions (`pos`, `read_pos`) are byte offsets into `input`.
+pub struct Lexer<'a> {
+    input: &'a str,
     size: usize,
     pos: usize,
     read_pos: usize,
     ch: char,
+    line: usize,
+    column: usize,
 }
 
-impl Lexer {
-    pub fn new(input: Vec<char>) -> Lexer {
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Lexer<'a> {
         let size = input.len();
         let mut lexer = Lexer {
             input,
@@ -22,11 +26,19 @@ impl Lexer {
             pos: 0,
             read_pos: 0,
             ch: ZERO_CHAR,
+            line: 1,
+            column: 0,
         };
         lexer.read_char();
         lexer
     }
 
+    /// Lexer over raw bytes, which must be valid UTF-8.
+    /// The bytes are validated but not copied.
+    pub fn from_bytes(input: &'a [u8]) -> Result<Lexer<'a>, std::str::Utf8Error> {
+        Ok(Lexer::new(std::str::from_utf8(input)?))
+    }
+
     pub fn run(&mut self) -> Vec<Token> {
         let mut tokens: Vec<Token> = Vec::new();
         loop {
@@ -43,17 +55,41 @@ impl Lexer {
     pub fn next_token(&mut self) -> Token {
         self.skip_whitespaces();
 
+        let (line, column) = (self.line, self.column);
+        let mut token = self.read_token();
+        token.set_position(line, column);
+        token
+    }
+
+    fn read_token(&mut self) -> Token {
         let token = match self.ch {
             '=' => {
                 if self.peek_char() == '=' {
                     self.read_char();
                     Token::new(token::EQ, "==".to_string())
+                } else if self.peek_char() == '>' {
+                    self.read_char();
+                    Token::new(token::FAT_ARROW, "=>".to_string())
                 } else {
                     Token::new(token::ASSIGN, '='.to_string())
                 }
             }
-            '+' => Token::new(token::PLUS, '+'.to_string()),
-            '-' => Token::new(token::MINUS, '-'.to_string()),
+            '+' => {
+                if self.peek_char() == '=' {
+                    self.read_char();
+                    Token::new(token::PLUS_ASSIGN, "+=".to_string())
+                } else {
+                    Token::new(token::PLUS, '+'.to_string())
+                }
+            }
+            '-' => {
+                if self.peek_char() == '=' {
+                    self.read_char();
+                    Token::new(token::MINUS_ASSIGN, "-=".to_string())
+                } else {
+                    Token::new(token::MINUS, '-'.to_string())
+                }
+            }
             '!' => {
                 if self.peek_char() == '=' {
                     self.read_char();
@@ -62,16 +98,74 @@ impl Lexer {
                     Token::new(token::BANG, '!'.to_string())
                 }
             }
-            '/' => Token::new(token::SLASH, '/'.to_string()),
-            '*' => Token::new(token::ASTRISK, '*'.to_string()),
+            '/' => {
+                if self.peek_char() == '=' {
+                    self.read_char();
+                    Token::new(token::SLASH_ASSIGN, "/=".to_string())
+                } else {
+                    Token::new(token::SLASH, '/'.to_string())
+                }
+            }
+            '*' => {
+                if self.peek_char() == '=' {
+                    self.read_char();
+                    Token::new(token::ASTRISK_ASSIGN, "*=".to_string())
+                } else {
+                    Token::new(token::ASTRISK, '*'.to_string())
+                }
+            }
             '<' => Token::new(token::LT, '<'.to_string()),
             '>' => Token::new(token::GT, '>'.to_string()),
+            '|' => Token::new(token::PIPE, '|'.to_string()),
             ';' => Token::new(token::SEMICOLON, ';'.to_string()),
+            ':' => Token::new(token::COLON, ':'.to_string()),
+            '.' => {
+                if self.peek_char() == '.' && self.peek_nth(1) == '.' {
+                    self.read_char();
+                    self.read_char();
+                    Token::new(token::ELLIPSIS, "...".to_string())
+                } else if self.peek_char() == '.' {
+                    self.read_char();
+                    Token::new(token::DOTDOT, "..".to_string())
+                } else {
+                    Token::new(token::ILLEGAL, '.'.to_string())
+                }
+            }
             ',' => Token::new(token::COMMA, ','.to_string()),
             '{' => Token::new(token::LBRACE, '{'.to_string()),
             '}' => Token::new(token::RBRACE, '}'.to_string()),
+            '[' => Token::new(token::LBRACKET, '['.to_string()),
+            ']' => Token::new(token::RBRACKET, ']'.to_string()),
             '(' => Token::new(token::LPAREN, '('.to_string()),
             ')' => Token::new(token::RPAREN, ')'.to_string()),
+            '"' if self.peek_char() == '"' && self.peek_nth(1) == '"' => {
+                let pos = self.pos;
+                match self.read_multiline_string() {
+                    Some(text) => Token::new(token::STRING, text),
+                    None => self.illegal_from(pos),
+                }
+            }
+            '"' => {
+                let pos = self.pos;
+                match self.read_string() {
+                    Some(segments) => match segments.as_slice() {
+                        [] => Token::new(token::STRING, "".to_string()),
+                        [Segment::Text(text)] => Token::new(token::STRING, text.clone()),
+                        _ => {
+                            let literal = self.input[pos + 1..self.pos].to_string();
+                            Token::interpolated(literal, segments)
+                        }
+                    },
+                    None => self.illegal_from(pos),
+                }
+            }
+            'r' if self.peek_char() == '"' || self.peek_char() == '#' => {
+                let pos = self.pos;
+                match self.read_raw_string() {
+                    Some(text) => Token::new(token::STRING, text),
+                    None => self.illegal_from(pos),
+                }
+            }
             ZERO_CHAR => Token::new(token::EOF, "".to_string()),
             _ => {
                 if self.is_letter(self.ch) {
@@ -91,20 +185,37 @@ impl Lexer {
     }
 
     fn read_char(&mut self) {
-        self.ch = if self.read_pos < self.size {
-            self.input[self.read_pos]
+        if self.ch == '\n' {
+            self.line += 1;
+            self.column = 1;
         } else {
-            ZERO_CHAR
-        };
+            self.column += 1;
+        }
         self.pos = self.read_pos;
-        self.read_pos += 1;
+        match self.input[self.read_pos..].chars().next() {
+            Some(c) => {
+                self.ch = c;
+                self.read_pos += c.len_utf8();
+            }
+            None => self.ch = ZERO_CHAR,
+        }
     }
 
-    fn peek_char(&mut self) -> char {
-        if self.read_pos < self.size {
-            return self.input[self.read_pos];
-        }
-        ZERO_CHAR
+    fn peek_char(&self) -> char {
+        self.peek_nth(0)
+    }
+
+    /// Returns the character n positions after the peeked one.
+    fn peek_nth(&self, n: usize) -> char {
+        self.input[self.read_pos..]
+            .chars()
+            .nth(n)
+            .unwrap_or(ZERO_CHAR)
+    }
+
+    /// ILLEGAL token with the source text from pos up to the current character.
+    fn illegal_from(&self, pos: usize) -> Token {
+        Token::new(token::ILLEGAL, self.input[pos..self.read_pos].to_string())
     }
 
     fn read_identifier(&mut self) -> String {
@@ -112,8 +223,7 @@ impl Lexer {
         while self.is_letter(self.ch) {
             self.read_char();
         }
-        let text: String = self.input[pos..self.pos].iter().collect();
-        text
+        self.input[pos..self.pos].to_string()
     }
 
     fn read_number(&mut self) -> String {
@@ -121,20 +231,133 @@ impl Lexer {
         while self.is_digit(self.ch) {
             self.read_char();
         }
-        let text: String = self.input[pos..self.pos].iter().collect();
-        text
+        self.input[pos..self.pos].to_string()
+    }
+
+    /// Reads a string literal, the current character is the opening quote.
+    /// Stops on the closing quote; returns None for an unterminated string
+    /// or a string with an unknown escape sequence.
+    /// The text is split into segments on every `${...}`; the expression
+    /// inside is tokenized in place, so its tokens keep their real positions.
+    fn read_string(&mut self) -> Option<Vec<Segment>> {
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut valid = true;
+        self.read_char();
+        loop {
+            match self.ch {
+                '"' => break,
+                '\\' => {
+                    match self.read_escape() {
+                        Some(c) => text.push(c),
+                        None if self.ch == ZERO_CHAR => return None,
+                        None => valid = false,
+                    }
+                    self.read_char();
+                }
+                '$' if self.peek_char() == '{' => {
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(text));
+                        text = String::new();
+                    }
+                    self.read_char();
+                    self.read_char();
+                    segments.push(Segment::Code(self.read_interpolation()?));
+                }
+                ZERO_CHAR => return None,
+                c => {
+                    text.push(c);
+                    self.read_char();
+                }
+            }
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        if valid {
+            Some(segments)
+        } else {
+            None
+        }
+    }
+
+    /// Reads tokens up to the `}` closing an interpolation (skipped).
+    fn read_interpolation(&mut self) -> Option<Vec<Token>> {
+        let mut tokens = Vec::new();
+        let mut depth = 0;
+        loop {
+            let t = self.next_token();
+            match t.name() {
+                token::EOF => return None,
+                token::LBRACE => depth += 1,
+                token::RBRACE if depth == 0 => return Some(tokens),
+                token::RBRACE => depth -= 1,
+                _ => (),
+            }
+            tokens.push(t);
+        }
+    }
+
+    /// Reads a raw string `r"..."` or `r#"..."#` (any number of hashes),
+    /// the current character is the 'r'. Escapes are not processed.
+    fn read_raw_string(&mut self) -> Option<String> {
+        let mut hashes = 0;
+        self.read_char();
+        while self.ch == '#' {
+            hashes += 1;
+            self.read_char();
+        }
+        if self.ch != '"' {
+            return None;
+        }
+        let pos = self.pos + 1;
+        loop {
+            self.read_char();
+            match self.ch {
+                '"' if (0..hashes).all(|n| self.peek_nth(n) == '#') => break,
+                ZERO_CHAR => return None,
+                _ => (),
+            }
+        }
+        let text = self.input[pos..self.pos].to_string();
+        for _ in 0..hashes {
+            self.read_char();
+        }
+        Some(text)
     }
 
-    fn read_string(&mut self) -> String {
+    /// Reads a triple-quoted string, the current character is the first quote.
+    /// The text is taken verbatim, then dedented (see `strip_indent`).
+    fn read_multiline_string(&mut self) -> Option<String> {
+        self.read_char();
+        self.read_char();
         let pos = self.pos + 1;
         loop {
             self.read_char();
-            if self.ch == '"' || self.ch == ZERO_CHAR {
-                break;
+            match self.ch {
+                '"' if self.peek_char() == '"' && self.peek_nth(1) == '"' => break,
+                ZERO_CHAR => return None,
+                _ => (),
             }
         }
-        let text: String = self.input[pos..self.pos].iter().collect();
-        text
+        let text = strip_indent(&self.input[pos..self.pos]);
+        self.read_char();
+        self.read_char();
+        Some(text)
+    }
+
+    fn read_escape(&mut self) -> Option<char> {
+        self.read_char();
+        match self.ch {
+            'n' => Some('\n'),
+            't' => Some('\t'),
+            'r' => Some('\r'),
+            '0' => Some('\0'),
+            '"' => Some('"'),
+            '\\' => Some('\\'),
+            '$' => Some('$'),
+            _ => None,
+        }
     }
 
     #[inline]
@@ -146,12 +369,12 @@ impl Lexer {
 
     #[inline]
     fn is_digit(&self, c: char) -> bool {
-        c >= '0' && c <= '9'
+        c.is_ascii_digit()
     }
 
     #[inline]
     fn is_letter(&self, c: char) -> bool {
-        (c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z') || c == '_'
+        c.is_ascii_alphabetic() || c == '_'
     }
 
     #[inline]
@@ -160,9 +383,67 @@ impl Lexer {
     }
 }
 
+/// Drops a blank first and last line and removes the longest
+/// whitespace prefix shared by all non-blank lines. Tabs and spaces
+/// only match themselves. Line endings ("\n" or "\r\n") become "\n".
+fn strip_indent(text: &str) -> String {
+    let mut lines: Vec<&str> = text
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .collect();
+    if lines.len() > 1 && lines[0].trim().is_empty() {
+        lines.remove(0);
+    }
+    if lines.len() > 1 && lines[lines.len() - 1].trim().is_empty() {
+        lines.pop();
+    }
+
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| &line[..line.len() - line.trim_start_matches([' ', '\t']).len()])
+        .fold(None, |common: Option<&str>, indent| match common {
+            None => Some(indent),
+            Some(common) => {
+                let n = common
+                    .bytes()
+                    .zip(indent.bytes())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                Some(&common[..n])
+            }
+        })
+        .unwrap_or("");
+
+    lines
+        .iter()
+        .map(|line| {
+            if line.trim().is_empty() {
+                ""
+            } else {
+                &line[indent.len()..]
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::token::TokenType;
+
+    /// Lexes input and checks each token's name and literal against expected;
+    /// returns the lexer positioned after the last expected token.
+    fn assert_tokens<'a>(input: &'a str, expected: &[(TokenType, &str)]) -> Lexer<'a> {
+        let mut lex = Lexer::new(input);
+        for (name, literal) in expected {
+            let retval = lex.next_token();
+            assert_eq!(retval.name(), *name);
+            assert_eq!(retval.literal(), *literal);
+        }
+        lex
+    }
 
     #[test]
     fn test_next_token() {
@@ -491,7 +772,7 @@ if (5 < 10) {
             },
         ];
 
-        let mut lex = Lexer::new(input.chars().collect());
+        let mut lex = Lexer::new(input);
         for t in &tests {
             let retval = lex.next_token();
             // println!("{}, {:?}", retval, t);
@@ -499,4 +780,424 @@ if (5 < 10) {
             assert_eq!(retval.literal(), t.expected_literal);
         }
     }
+
+    #[test]
+    fn test_eq_and_not_eq_names() {
+        assert_ne!(token::EQ, token::NOT_EQ);
+
+        let mut lex = Lexer::new("== !=");
+        assert_eq!(lex.next_token().name(), token::EQ);
+        assert_eq!(lex.next_token().name(), token::NOT_EQ);
+    }
+
+    #[test]
+    fn test_assignment_tokens() {
+        let input = r"x = x + 1;
+x += 2;
+x -= 3;
+x *= 4;
+x /= 5;
+arr[0] = x;
+";
+
+        let tests = [
+            (token::IDENT, "x"),
+            (token::ASSIGN, "="),
+            (token::IDENT, "x"),
+            (token::PLUS, "+"),
+            (token::INT, "1"),
+            (token::SEMICOLON, ";"),
+            (token::IDENT, "x"),
+            (token::PLUS_ASSIGN, "+="),
+            (token::INT, "2"),
+            (token::SEMICOLON, ";"),
+            (token::IDENT, "x"),
+            (token::MINUS_ASSIGN, "-="),
+            (token::INT, "3"),
+            (token::SEMICOLON, ";"),
+            (token::IDENT, "x"),
+            (token::ASTRISK_ASSIGN, "*="),
+            (token::INT, "4"),
+            (token::SEMICOLON, ";"),
+            (token::IDENT, "x"),
+            (token::SLASH_ASSIGN, "/="),
+            (token::INT, "5"),
+            (token::SEMICOLON, ";"),
+            (token::IDENT, "arr"),
+            (token::LBRACKET, "["),
+            (token::INT, "0"),
+            (token::RBRACKET, "]"),
+            (token::ASSIGN, "="),
+            (token::IDENT, "x"),
+            (token::SEMICOLON, ";"),
+            (token::EOF, ""),
+        ];
+
+        assert_tokens(input, &tests);
+    }
+
+    #[test]
+    fn test_keywords() {
+        let input =
+            "fn let const true false if else return throw try catch finally macro match constant";
+
+        let tests = [
+            (token::FUNCTION, "fn"),
+            (token::LET, "let"),
+            (token::CONST, "const"),
+            (token::TRUE, "true"),
+            (token::FALSE, "false"),
+            (token::IF, "if"),
+            (token::ELSE, "else"),
+            (token::RETURN, "return"),
+            (token::THROW, "throw"),
+            (token::TRY, "try"),
+            (token::CATCH, "catch"),
+            (token::FINALLY, "finally"),
+            (token::MACRO, "macro"),
+            (token::MATCH, "match"),
+            (token::IDENT, "constant"),
+            (token::EOF, ""),
+        ];
+
+        assert_tokens(input, &tests);
+    }
+
+    #[test]
+    fn test_token_position() {
+        let input = "let x = 5;\n\n  x += 10;\n";
+
+        let tests = [
+            (token::LET, 1, 1),
+            (token::IDENT, 1, 5),
+            (token::ASSIGN, 1, 7),
+            (token::INT, 1, 9),
+            (token::SEMICOLON, 1, 10),
+            (token::IDENT, 3, 3),
+            (token::PLUS_ASSIGN, 3, 5),
+            (token::INT, 3, 8),
+            (token::SEMICOLON, 3, 10),
+            (token::EOF, 4, 1),
+        ];
+
+        let mut lex = Lexer::new(input);
+        for (name, line, column) in tests.iter() {
+            let retval = lex.next_token();
+            assert_eq!(retval.name(), *name);
+            assert_eq!((retval.line(), retval.column()), (*line, *column));
+        }
+    }
+
+    #[test]
+    fn test_string() {
+        let input = r#""foobar" "foo bar" "" "tab\tquote\"slash\\" "bad\q" "open"#;
+
+        let tests = [
+            (token::STRING, "foobar"),
+            (token::STRING, "foo bar"),
+            (token::STRING, ""),
+            (token::STRING, "tab\tquote\"slash\\"),
+            (token::ILLEGAL, "\"bad\\q\""),
+            (token::ILLEGAL, "\"open"),
+            (token::EOF, ""),
+        ];
+
+        assert_tokens(input, &tests);
+    }
+
+    #[test]
+    fn test_interpolated_string() {
+        let input =
+            "\"Hello ${name}, you are ${age + 1}\" \"${\"a\"}\" \"\\${x}\"\n\"id: ${x @ {}}\"";
+
+        let mut lex = Lexer::new(input);
+
+        let t = lex.next_token();
+        assert_eq!(t.name(), token::INTERP_STRING);
+        assert_eq!(t.literal(), "Hello ${name}, you are ${age + 1}");
+        let segments = t.segments();
+        assert_eq!(segments.len(), 4);
+        assert_eq!(segments[0], Segment::Text("Hello ".to_string()));
+        assert_eq!(segments[2], Segment::Text(", you are ".to_string()));
+        match &segments[3] {
+            Segment::Code(tokens) => {
+                let names: Vec<_> = tokens.iter().map(|t| t.name()).collect();
+                assert_eq!(names, [token::IDENT, token::PLUS, token::INT]);
+                assert_eq!((tokens[0].line(), tokens[0].column()), (1, 27));
+            }
+            s => panic!("expected code segment, got {:?}", s),
+        }
+
+        let t = lex.next_token();
+        assert_eq!(t.name(), token::INTERP_STRING);
+        match &t.segments()[0] {
+            Segment::Code(tokens) => {
+                assert_eq!(tokens.len(), 1);
+                assert_eq!(tokens[0].name(), token::STRING);
+                assert_eq!(tokens[0].literal(), "a");
+            }
+            s => panic!("expected code segment, got {:?}", s),
+        }
+
+        let t = lex.next_token();
+        assert_eq!(t.name(), token::STRING);
+        assert_eq!(t.literal(), "${x}");
+
+        let t = lex.next_token();
+        assert_eq!(t.name(), token::INTERP_STRING);
+        match &t.segments()[1] {
+            Segment::Code(tokens) => {
+                assert_eq!(tokens[1].name(), token::ILLEGAL);
+                assert_eq!((tokens[1].line(), tokens[1].column()), (2, 10));
+                assert_eq!(tokens[2].name(), token::LBRACE);
+                assert_eq!(tokens[3].name(), token::RBRACE);
+            }
+            s => panic!("expected code segment, got {:?}", s),
+        }
+
+        assert!(lex.next_token().is_eof());
+    }
+
+    #[test]
+    fn test_raw_string() {
+        let input = r####"r"C:\path\n" r#"say "hi" \t"# r##"a "# b"## r"open"####;
+
+        let tests = [
+            (token::STRING, r"C:\path\n"),
+            (token::STRING, r#"say "hi" \t"#),
+            (token::STRING, r###"a "# b"###),
+            (token::ILLEGAL, "r\"open"),
+            (token::EOF, ""),
+        ];
+
+        assert_tokens(input, &tests);
+    }
+
+    #[test]
+    fn test_multiline_string() {
+        let input = r#"let q = """
+    SELECT *
+      FROM t\n
+
+    WHERE "id" = 1
+    """;
+"""one line""" ""
+"#;
+
+        let tests = [
+            (token::LET, "let"),
+            (token::IDENT, "q"),
+            (token::ASSIGN, "="),
+            (token::STRING, "SELECT *\n  FROM t\\n\n\nWHERE \"id\" = 1"),
+            (token::SEMICOLON, ";"),
+            (token::STRING, "one line"),
+            (token::STRING, ""),
+            (token::EOF, ""),
+        ];
+
+        let mut lex = assert_tokens(input, &tests);
+        assert_eq!(lex.next_token().line(), 8);
+
+        let input = "\"\"\"\n\tfoo\n    bar\n\"\"\" \"\"\"\n\t  a\n\n\t    b\n\t\"\"\"";
+        let tests = [
+            (token::STRING, "\tfoo\n    bar"),
+            (token::STRING, "a\n\n  b"),
+            (token::EOF, ""),
+        ];
+        assert_tokens(input, &tests);
+
+        let input = "\"\"\"\r\n  a\r\n  b\r\n  \"\"\"";
+        let tests = [(token::STRING, "a\nb"), (token::EOF, "")];
+        assert_tokens(input, &tests);
+    }
+
+    #[test]
+    fn test_match() {
+        let input = r"match xs {
+    [head, ..rest] if head > 0 => head,
+    _ => 0,
+}";
+
+        let tests = [
+            (token::MATCH, "match"),
+            (token::IDENT, "xs"),
+            (token::LBRACE, "{"),
+            (token::LBRACKET, "["),
+            (token::IDENT, "head"),
+            (token::COMMA, ","),
+            (token::DOTDOT, ".."),
+            (token::IDENT, "rest"),
+            (token::RBRACKET, "]"),
+            (token::IF, "if"),
+            (token::IDENT, "head"),
+            (token::GT, ">"),
+            (token::INT, "0"),
+            (token::FAT_ARROW, "=>"),
+            (token::IDENT, "head"),
+            (token::COMMA, ","),
+            (token::IDENT, "_"),
+            (token::FAT_ARROW, "=>"),
+            (token::INT, "0"),
+            (token::COMMA, ","),
+            (token::RBRACE, "}"),
+            (token::EOF, ""),
+        ];
+
+        assert_tokens(input, &tests);
+    }
+
+    #[test]
+    fn test_destructuring_let() {
+        let input = r#"let [a, b] = pair;
+let {"x": x, "y": y} = point;"#;
+
+        let tests = [
+            (token::LET, "let"),
+            (token::LBRACKET, "["),
+            (token::IDENT, "a"),
+            (token::COMMA, ","),
+            (token::IDENT, "b"),
+            (token::RBRACKET, "]"),
+            (token::ASSIGN, "="),
+            (token::IDENT, "pair"),
+            (token::SEMICOLON, ";"),
+            (token::LET, "let"),
+            (token::LBRACE, "{"),
+            (token::STRING, "x"),
+            (token::COLON, ":"),
+            (token::IDENT, "x"),
+            (token::COMMA, ","),
+            (token::STRING, "y"),
+            (token::COLON, ":"),
+            (token::IDENT, "y"),
+            (token::RBRACE, "}"),
+            (token::ASSIGN, "="),
+            (token::IDENT, "point"),
+            (token::SEMICOLON, ";"),
+            (token::EOF, ""),
+        ];
+
+        assert_tokens(input, &tests);
+    }
+
+    #[test]
+    fn test_function_parameters() {
+        let input = "fn(x, y = 10, ...rest) {}; f(...args); [..xs];";
+
+        let tests = [
+            (token::FUNCTION, "fn"),
+            (token::LPAREN, "("),
+            (token::IDENT, "x"),
+            (token::COMMA, ","),
+            (token::IDENT, "y"),
+            (token::ASSIGN, "="),
+            (token::INT, "10"),
+            (token::COMMA, ","),
+            (token::ELLIPSIS, "..."),
+            (token::IDENT, "rest"),
+            (token::RPAREN, ")"),
+            (token::LBRACE, "{"),
+            (token::RBRACE, "}"),
+            (token::SEMICOLON, ";"),
+            (token::IDENT, "f"),
+            (token::LPAREN, "("),
+            (token::ELLIPSIS, "..."),
+            (token::IDENT, "args"),
+            (token::RPAREN, ")"),
+            (token::SEMICOLON, ";"),
+            (token::LBRACKET, "["),
+            (token::DOTDOT, ".."),
+            (token::IDENT, "xs"),
+            (token::RBRACKET, "]"),
+            (token::SEMICOLON, ";"),
+            (token::EOF, ""),
+        ];
+
+        assert_tokens(input, &tests);
+    }
+
+    #[test]
+    fn test_lambda() {
+        let input = "|x, y| x + y; x => x * 2; || 1; x = >y;";
+
+        let tests = [
+            (token::PIPE, "|"),
+            (token::IDENT, "x"),
+            (token::COMMA, ","),
+            (token::IDENT, "y"),
+            (token::PIPE, "|"),
+            (token::IDENT, "x"),
+            (token::PLUS, "+"),
+            (token::IDENT, "y"),
+            (token::SEMICOLON, ";"),
+            (token::IDENT, "x"),
+            (token::FAT_ARROW, "=>"),
+            (token::IDENT, "x"),
+            (token::ASTRISK, "*"),
+            (token::INT, "2"),
+            (token::SEMICOLON, ";"),
+            (token::PIPE, "|"),
+            (token::PIPE, "|"),
+            (token::INT, "1"),
+            (token::SEMICOLON, ";"),
+            (token::IDENT, "x"),
+            (token::ASSIGN, "="),
+            (token::GT, ">"),
+            (token::IDENT, "y"),
+            (token::SEMICOLON, ";"),
+            (token::EOF, ""),
+        ];
+
+        assert_tokens(input, &tests);
+    }
+
+    #[test]
+    fn test_long_number() {
+        let input = "9223372036854775807 123456789012345678901234567890 -1";
+
+        let tests = [
+            (token::INT, "9223372036854775807"),
+            (token::INT, "123456789012345678901234567890"),
+            (token::MINUS, "-"),
+            (token::INT, "1"),
+            (token::EOF, ""),
+        ];
+
+        assert_tokens(input, &tests);
+    }
+
+    #[test]
+    fn test_mapped_source() {
+        use crate::shared::file;
+
+        let path = format!(
+            "{}/lexer-{}.src",
+            std::env::temp_dir().display(),
+            std::process::id()
+        );
+        file::write_file(&path, "let s = \"zażółć\"; ż\n".as_bytes()).unwrap();
+        // SAFETY: the file is not touched again until the lexer is done.
+        let view = unsafe { file::map(&path) }.unwrap();
+
+        let tests = [
+            (token::LET, "let", 1),
+            (token::IDENT, "s", 5),
+            (token::ASSIGN, "=", 7),
+            (token::STRING, "zażółć", 9),
+            (token::SEMICOLON, ";", 17),
+            (token::ILLEGAL, "ż", 19),
+            (token::EOF, "", 1),
+        ];
+
+        let mut lex = Lexer::from_bytes(&view).unwrap();
+        for (name, literal, column) in tests.iter() {
+            let retval = lex.next_token();
+            assert_eq!(retval.name(), *name);
+            assert_eq!(retval.literal(), *literal);
+            assert_eq!(retval.column(), *column);
+        }
+        file::remove(&path).unwrap();
+
+        assert!(Lexer::from_bytes(b"let x = \xff;").is_err());
+    }
 }
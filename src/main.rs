@@ -7,10 +7,30 @@ mod shared;
 mod token;
 
 fn main() {
-    let code = "let five = 5;".to_owned();
+    // Source file given as the first argument is mapped and lexed in place.
+    // SAFETY: the source file must not be modified while it is being lexed;
+    // like any compiler input, editing it mid-run is not supported.
+    let view = match std::env::args().nth(1) {
+        Some(path) => match unsafe { shared::file::map(&path) } {
+            Ok(view) => Some(view),
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
 
-    let characters: Vec<char> = code.chars().collect();
-    let mut lex = lexer::Lexer::new(characters);
+    let mut lex = match &view {
+        Some(view) => match lexer::Lexer::from_bytes(view) {
+            Ok(lex) => lex,
+            Err(err) => {
+                eprintln!("invalid UTF-8 in source: {}", err);
+                std::process::exit(1);
+            }
+        },
+        None => lexer::Lexer::new("let five = 5;"),
+    };
     let tokens = lex.run();
     for t in tokens {
         println!("{}", t);
@@ -14,7 +14,7 @@ pub enum Region {
    Local,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 pub struct DateTime {
    region: Region,
    tstamp: i64,
@@ -156,7 +156,7 @@ impl DateTime {
             return dt_from_tm(Region::Utc, ts, &tm);
          }
       }
-      self.clone()
+      *self
    }
 
    /// Utworzenie obiektu w regionie Local (jeśli obiekt jest UTC).
@@ -169,7 +169,7 @@ impl DateTime {
             return dt_from_tm(Region::Utc, ts, &tm);
          }         
       }
-      self.clone()
+      *self
    }
 
    
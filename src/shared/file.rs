@@ -1,10 +1,16 @@
 #![allow(dead_code)]
 
 extern crate libc;
-use std::ffi::CString;
+use std::error;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::io;
+use std::ops::Deref;
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use crate::shared::datime;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum FileType {
    Unknown,
    Regular,
@@ -16,6 +22,117 @@ pub enum FileType {
    Socket,
 }
 
+impl FileType {
+   /// Nazwa typu widoczna w skryptach.
+   pub fn as_str(&self) -> &'static str {
+      match self {
+         FileType::Unknown => "unknown",
+         FileType::Regular => "file",
+         FileType::Directory => "dir",
+         FileType::CharacterSpecial => "char_device",
+         FileType::BlockSpecial => "block_device",
+         FileType::Fifo => "fifo",
+         FileType::Symlink => "symlink",
+         FileType::Socket => "socket",
+      }
+   }
+}
+
+impl fmt::Display for FileType {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      write!(f, "{}", self.as_str())
+   }
+}
+
+/// Typ pliku na podstawie pola 'st_mode'.
+fn stat2enum(mode: u32) -> FileType {
+   match mode & libc::S_IFMT {
+      libc::S_IFREG  => FileType::Regular,
+      libc::S_IFDIR  => FileType::Directory,
+      libc::S_IFCHR  => FileType::CharacterSpecial,
+      libc::S_IFBLK  => FileType::BlockSpecial,
+      libc::S_IFIFO  => FileType::Fifo,
+      libc::S_IFLNK  => FileType::Symlink,
+      libc::S_IFSOCK => FileType::Socket,
+                   _ => FileType::Unknown
+   }
+}
+
+/// Typ pliku na podstawie pola 'd_type' z 'readdir'.
+fn dtype2enum(dtype: u8) -> FileType {
+   match dtype {
+      libc::DT_REG  => FileType::Regular,
+      libc::DT_DIR  => FileType::Directory,
+      libc::DT_CHR  => FileType::CharacterSpecial,
+      libc::DT_BLK  => FileType::BlockSpecial,
+      libc::DT_FIFO => FileType::Fifo,
+      libc::DT_LNK  => FileType::Symlink,
+      libc::DT_SOCK => FileType::Socket,
+                  _ => FileType::Unknown
+   }
+}
+
+/// Błąd operacji na pliku.
+/// Przechowuje numer błędu (errno), nazwę operacji i ścieżkę pliku.
+#[derive(Debug)]
+pub struct FileError {
+   errno: i32,
+   op: &'static str,
+   path: String,
+}
+
+impl FileError {
+   pub fn new(errno: i32, op: &'static str, path: &str) -> FileError {
+      FileError { errno, op, path: String::from(path) }
+   }
+
+   /// Błąd z bieżącą wartością errno (po nieudanym wywołaniu libc).
+   fn last(op: &'static str, path: &str) -> FileError {
+      let errno = io::Error::last_os_error().raw_os_error().unwrap_or(0);
+      FileError::new(errno, op, path)
+   }
+
+   pub fn errno(&self) -> i32 {
+      self.errno
+   }
+
+   pub fn op(&self) -> &str {
+      self.op
+   }
+
+   pub fn path(&self) -> &str {
+      &self.path
+   }
+
+   pub fn kind(&self) -> io::ErrorKind {
+      io::Error::from_raw_os_error(self.errno).kind()
+   }
+}
+
+impl fmt::Display for FileError {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      write!(f, "{} '{}': {}", self.op, self.path, io::Error::from_raw_os_error(self.errno))
+   }
+}
+
+impl error::Error for FileError {}
+
+impl From<FileError> for io::Error {
+   fn from(err: FileError) -> io::Error {
+      io::Error::new(err.kind(), err)
+   }
+}
+
+/// Konwersja ścieżki na napis C.
+/// Ścieżka zawierająca bajt zerowy jest błędem EINVAL.
+fn cpath(op: &'static str, path: &str) -> Result<CString, FileError> {
+   CString::new(path).map_err(|_| FileError::new(libc::EINVAL, op, path))
+}
+
+/// Porcja, o którą powiększany jest bufor w 'read_all'.
+const READ_CHUNK: usize = 8192;
+
+#[derive(Debug)]
 pub struct File {
    fd: i32,
    fpath: String,
@@ -28,16 +145,149 @@ pub fn new(path: &str) -> File {
    }
 }
 
-pub fn exists(path: &str) -> bool {
-   if let Ok(cstr) = CString::new(path) {
-      unsafe {
-         if libc::access(cstr.as_ptr(), libc::F_OK) == 0 {
-            return true;
-         }
+/// Opcje otwarcia pliku (odpowiedniki flag 'open').
+/// Domyślnie nic nie jest włączone, a nowe pliki dostają prawa 0664.
+#[derive(Clone, Debug)]
+pub struct OpenOptions {
+   read: bool,
+   write: bool,
+   append: bool,
+   truncate: bool,
+   create: bool,
+   create_new: bool,
+   cloexec: bool,
+   nofollow: bool,
+   mode: u32,
+}
+
+impl OpenOptions {
+   pub fn new() -> OpenOptions {
+      OpenOptions {
+         read: false,
+         write: false,
+         append: false,
+         truncate: false,
+         create: false,
+         create_new: false,
+         cloexec: false,
+         nofollow: false,
+         mode: 0o664,
       }
-      perror(fpos!());
    }
-   false
+
+   pub fn read(&mut self, on: bool) -> &mut OpenOptions {
+      self.read = on;
+      self
+   }
+
+   pub fn write(&mut self, on: bool) -> &mut OpenOptions {
+      self.write = on;
+      self
+   }
+
+   /// Zapis zawsze na końcu pliku (O_APPEND), implikuje zapis.
+   pub fn append(&mut self, on: bool) -> &mut OpenOptions {
+      self.append = on;
+      self
+   }
+
+   /// Obcięcie pliku do zera (O_TRUNC), wymaga zapisu.
+   pub fn truncate(&mut self, on: bool) -> &mut OpenOptions {
+      self.truncate = on;
+      self
+   }
+
+   /// Utworzenie pliku jeśli nie istnieje (O_CREAT).
+   pub fn create(&mut self, on: bool) -> &mut OpenOptions {
+      self.create = on;
+      self
+   }
+
+   /// Utworzenie pliku, błąd EEXIST jeśli już istnieje (O_CREAT|O_EXCL).
+   pub fn create_new(&mut self, on: bool) -> &mut OpenOptions {
+      self.create_new = on;
+      self
+   }
+
+   /// Zamknięcie deskryptora przy 'exec' (O_CLOEXEC).
+   pub fn cloexec(&mut self, on: bool) -> &mut OpenOptions {
+      self.cloexec = on;
+      self
+   }
+
+   /// Błąd ELOOP jeśli ostatni element ścieżki to link symboliczny (O_NOFOLLOW).
+   pub fn nofollow(&mut self, on: bool) -> &mut OpenOptions {
+      self.nofollow = on;
+      self
+   }
+
+   /// Prawa dostępu nowo tworzonego pliku (przed nałożeniem umask).
+   pub fn mode(&mut self, mode: u32) -> &mut OpenOptions {
+      self.mode = mode;
+      self
+   }
+
+   /// Otwiera plik określony przez przysłaną ścieżkę.
+   pub fn open(&self, path: &str) -> Result<File, FileError> {
+      let mut file = new(path);
+      file.open_with(self)?;
+      Ok(file)
+   }
+
+   /// Flagi dla 'open'; None jeśli kombinacja opcji nie ma sensu
+   /// (brak odczytu i zapisu, obcięcie lub tworzenie bez zapisu).
+   fn flags(&self) -> Option<i32> {
+      let write = self.write || self.append;
+      let mut oflag = match (self.read, write) {
+         (true, true) => libc::O_RDWR,
+         (true, false) => libc::O_RDONLY,
+         (false, true) => libc::O_WRONLY,
+         (false, false) => return None,
+      };
+      if !write && (self.truncate || self.create || self.create_new) {
+         return None;
+      }
+      if self.append {
+         oflag |= libc::O_APPEND;
+      }
+      if self.truncate {
+         oflag |= libc::O_TRUNC;
+      }
+      if self.create_new {
+         oflag |= libc::O_CREAT | libc::O_EXCL;
+      } else if self.create {
+         oflag |= libc::O_CREAT;
+      }
+      if self.cloexec {
+         oflag |= libc::O_CLOEXEC;
+      }
+      if self.nofollow {
+         oflag |= libc::O_NOFOLLOW;
+      }
+      Some(oflag)
+   }
+}
+
+impl Default for OpenOptions {
+   fn default() -> OpenOptions {
+      OpenOptions::new()
+   }
+}
+
+/// Sprawdza czy plik istnieje.
+/// Brak pliku (ENOENT, ENOTDIR) to Ok(false), pozostałe błędy są zwracane.
+pub fn exists(path: &str) -> Result<bool, FileError> {
+   let cstr = cpath("access", path)?;
+   unsafe {
+      if libc::access(cstr.as_ptr(), libc::F_OK) == 0 {
+         return Ok(true);
+      }
+   }
+   let err = FileError::last("access", path);
+   match err.errno {
+      libc::ENOENT | libc::ENOTDIR => Ok(false),
+      _ => Err(err),
+   }
 }
 
 pub fn readable(path: &str) -> bool {
@@ -77,250 +327,1413 @@ pub fn executable(path: &str) -> bool {
 
 /// Usuwa z dysku plik określonego
 /// przez przysłaną ścieżkę.
-pub fn remove(path: &str) -> bool {
-   if let Ok(cstr) = CString::new(path) {
-         
-      unsafe {
-         if libc::remove(cstr.as_ptr()) == 0 {
-            return true;
-         }
+pub fn remove(path: &str) -> Result<(), FileError> {
+   let cstr = cpath("remove", path)?;
+   unsafe {
+      if libc::remove(cstr.as_ptr()) == 0 {
+         return Ok(());
       }
-      perror(fpos!());
    }
-   false
+   Err(FileError::last("remove", path))
 }
 
 /// Zmiana nazwy pliku (lub move).
-pub fn rename(src: &str, dst: &str) -> bool {
-   if let Ok(csrc) = CString::new(src) {
-      if let Ok(cdst) = CString::new(dst) {
+/// W błędzie zapisywana jest ścieżka źródłowa.
+pub fn rename(src: &str, dst: &str) -> Result<(), FileError> {
+   let csrc = cpath("rename", src)?;
+   let cdst = cpath("rename", dst)?;
+   unsafe {
+      if libc::rename(csrc.as_ptr(), cdst.as_ptr()) != -1 {
+         return Ok(());
+      }
+   }
+   Err(FileError::last("rename", src))
+}
+
+/// Odczyt całej zawartości pliku określonego przez ścieżkę.
+pub fn read_file(path: &str) -> Result<Vec<u8>, FileError> {
+   OpenOptions::new().read(true).open(path)?.read_all()
+}
+
+/// Zapis bajtów do pliku. Plik jest tworzony lub obcinany.
+pub fn write_file(path: &str, bytes: &[u8]) -> Result<usize, FileError> {
+   OpenOptions::new().write(true).create(true).truncate(true).open(path)?.write_bytes(bytes)
+}
+
+/// Dopisanie bajtów na końcu pliku. Plik jest tworzony jeśli nie istnieje.
+pub fn append_file(path: &str, bytes: &[u8]) -> Result<usize, FileError> {
+   OpenOptions::new().append(true).create(true).open(path)?.write_bytes(bytes)
+}
+
+/// Odczyt pliku jako listy linii (bez znaków końca linii, także '\r\n').
+/// Niepoprawne sekwencje UTF-8 zastępowane są znakiem U+FFFD.
+pub fn lines(path: &str) -> Result<Vec<String>, FileError> {
+   let content = read_file(path)?;
+   let text = String::from_utf8_lossy(&content);
+   Ok(text.lines().map(String::from).collect())
+}
+
+/// Typ pliku określonego przez ścieżkę (link symboliczny nie jest rozwijany).
+pub fn file_type(path: &str) -> Result<FileType, FileError> {
+   Ok(symlink_metadata(path)?.ftype())
+}
+
+/// Licznik nadający unikalne nazwy plikom tymczasowym w tym procesie.
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Atomowy zapis pliku: po awarii plik ma starą albo nową zawartość,
+/// nigdy obciętą. Bajty zapisywane są do pliku tymczasowego w tym samym
+/// katalogu, który po 'fsync' zastępuje plik docelowy ('rename'),
+/// a na koniec 'fsync' robiony jest też na katalogu.
+/// Prawa dostępu istniejącego pliku są zachowywane. Jeśli ścieżka
+/// jest linkiem symbolicznym zapisywany jest plik, na który wskazuje
+/// (także jeszcze nieistniejący - pierwszy zapis przez wiszący link tworzy plik).
+pub fn write_atomic(path: &str, bytes: &[u8]) -> Result<(), FileError> {
+   let target = link_target(path)?;
+   let mode = metadata(&target).map(|meta| Some(meta.mode())).or_else(|err| match err.errno {
+      libc::ENOENT => Ok(None),
+      _ => Err(err),
+   })?;
+   let (dir, name) = match target.rfind('/') {
+      Some(0) => ("/", &target[1..]),
+      Some(idx) => (&target[..idx], &target[idx + 1..]),
+      None => (".", &target[..]),
+   };
+
+   let (tmp, file) = loop {
+      let n = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+      let tmp = format!("{}/.{}.{}.{}.tmp", dir, name, std::process::id(), n);
+      match OpenOptions::new().write(true).create_new(true).mode(mode.unwrap_or(0o666)).open(&tmp) {
+         Err(err) if err.errno == libc::EEXIST => continue,
+         result => break (tmp, result?),
+      }
+   };
+
+   if let Err(err) = replace_with(file, mode, bytes, &target) {
+      let _ = remove(&tmp);
+      return Err(err);
+   }
+   OpenOptions::new().read(true).open(dir)?.sync()
+}
+
+/// Ścieżka pliku wskazywanego przez (łańcuch) linków symbolicznych.
+/// W odróżnieniu od 'realpath' plik docelowy nie musi istnieć.
+/// Względny cel linku liczony jest od katalogu, w którym leży link.
+fn link_target(path: &str) -> Result<String, FileError> {
+   let mut target = String::from(path);
+   // jak SYMLOOP_MAX w Linuksie
+   for _ in 0..40 {
+      match symlink_metadata(&target) {
+         Ok(meta) if meta.ftype() == FileType::Symlink => (),
+         Err(err) if err.errno != libc::ENOENT => return Err(err),
+         _ => return Ok(target),
+      }
+      let link = readlink(&target)?;
+      target = match target.rfind('/') {
+         Some(idx) if !link.starts_with('/') => format!("{}{}", &target[..idx + 1], link),
+         _ => link,
+      };
+   }
+   Err(FileError::new(libc::ELOOP, "readlink", path))
+}
+
+/// Zapis i 'fsync' pliku tymczasowego, a potem zastąpienie nim pliku docelowego.
+fn replace_with(file: File, mode: Option<u32>, bytes: &[u8], target: &str) -> Result<(), FileError> {
+   if let Some(mode) = mode {
+      file.chmod(mode)?;
+   }
+   file.write_bytes(bytes)?;
+   file.sync()?;
+   rename(file.path(), target)
+}
+
+/// Informacje o pliku; linki symboliczne są rozwijane.
+fn stat(path: &str) -> Result<libc::stat, FileError> {
+   let cstr = cpath("stat", path)?;
+   unsafe {
+      let mut status: libc::stat = std::mem::zeroed();
+      if libc::stat(cstr.as_ptr(), &mut status) != -1 {
+         return Ok(status);
+      }
+   }
+   Err(FileError::last("stat", path))
+}
+
+/// Informacje o pliku bez podążania za linkiem symbolicznym.
+fn lstat(path: &str) -> Result<libc::stat, FileError> {
+   let cstr = cpath("lstat", path)?;
+   unsafe {
+      let mut status: libc::stat = std::mem::zeroed();
+      if libc::lstat(cstr.as_ptr(), &mut status) != -1 {
+         return Ok(status);
+      }
+   }
+   Err(FileError::last("lstat", path))
+}
+
+/// Metadane pliku; dla linku symbolicznego - pliku na który wskazuje.
+pub fn metadata(path: &str) -> Result<Metadata, FileError> {
+   Metadata::from_stat(&stat(path)?, path)
+}
+
+/// Metadane pliku; dla linku symbolicznego - samego linku.
+pub fn symlink_metadata(path: &str) -> Result<Metadata, FileError> {
+   Metadata::from_stat(&lstat(path)?, path)
+}
+
+/// Tworzy link symboliczny 'link' wskazujący na 'target'.
+pub fn symlink(target: &str, link: &str) -> Result<(), FileError> {
+   let ctarget = cpath("symlink", target)?;
+   let clink = cpath("symlink", link)?;
+   unsafe {
+      if libc::symlink(ctarget.as_ptr(), clink.as_ptr()) == 0 {
+         return Ok(());
+      }
+   }
+   Err(FileError::last("symlink", link))
+}
+
+/// Odczyt ścieżki na którą wskazuje link symboliczny.
+pub fn readlink(path: &str) -> Result<String, FileError> {
+   let cstr = cpath("readlink", path)?;
+   let mut buffer: Vec<u8> = vec![0; 256];
+   loop {
+      let n = unsafe { libc::readlink(cstr.as_ptr(), buffer.as_mut_ptr() as *mut libc::c_char, buffer.len()) };
+      if n == -1 {
+         return Err(FileError::last("readlink", path));
+      }
+      // wynik mógł zostać obcięty - powtarzamy z większym buforem
+      let n = n as usize;
+      if n < buffer.len() {
+         buffer.truncate(n);
+         return Ok(String::from_utf8_lossy(&buffer).into_owned());
+      }
+      buffer.resize(2 * buffer.len(), 0);
+   }
+}
+
+/// Ścieżka bezwzględna bez linków symbolicznych, '.', '..' i podwójnych '/'.
+pub fn realpath(path: &str) -> Result<String, FileError> {
+   let cstr = cpath("realpath", path)?;
+   unsafe {
+      let resolved = libc::realpath(cstr.as_ptr(), std::ptr::null_mut());
+      if resolved.is_null() {
+         return Err(FileError::last("realpath", path));
+      }
+      let text = CStr::from_ptr(resolved).to_string_lossy().into_owned();
+      libc::free(resolved as *mut libc::c_void);
+      Ok(text)
+   }
+}
+
+/// Informacje o pliku (odpowiednik struktury 'stat').
+#[derive(Debug, Clone, Copy)]
+pub struct Metadata {
+   ftype: FileType,
+   mode: u32,
+   inode: u64,
+   dev: u64,
+   rdev: u64,
+   nlink: u64,
+   uid: u32,
+   gid: u32,
+   size: i64,
+   atime: datime::DateTime,
+   mtime: datime::DateTime,
+   ctime: datime::DateTime,
+   blksize: i64,
+   blocks: i64,
+}
+
+impl Metadata {
+   // Typy pól 'stat' zależą od platformy (np. st_nlink to u32 na aarch64,
+   // st_blksize to i32 na aarch64 i 32-bitowych), stąd jawne rzutowania.
+   #[allow(clippy::unnecessary_cast)]
+   fn from_stat(st: &libc::stat, path: &str) -> Result<Metadata, FileError> {
+      let time = |ts: i64| datime::local_from_tstamp(ts).ok_or_else(|| FileError::new(libc::EOVERFLOW, "stat", path));
+      Ok(Metadata {
+         ftype: stat2enum(st.st_mode),
+         mode: st.st_mode & 0o7777,
+         inode: st.st_ino as u64,
+         dev: st.st_dev as u64,
+         rdev: st.st_rdev as u64,
+         nlink: st.st_nlink as u64,
+         uid: st.st_uid,
+         gid: st.st_gid,
+         size: st.st_size as i64,
+         atime: time(st.st_atime as i64)?,
+         mtime: time(st.st_mtime as i64)?,
+         ctime: time(st.st_ctime as i64)?,
+         blksize: st.st_blksize as i64,
+         blocks: st.st_blocks as i64,
+      })
+   }
+
+   pub fn ftype(&self) -> FileType {
+      self.ftype
+   }
+
+   /// Prawa dostępu (bity 0o7777 z 'st_mode').
+   pub fn mode(&self) -> u32 {
+      self.mode
+   }
+
+   pub fn inode(&self) -> u64 {
+      self.inode
+   }
+
+   pub fn dev(&self) -> u64 {
+      self.dev
+   }
+
+   /// Numer urządzenia dla plików specjalnych (znakowych i blokowych).
+   pub fn rdev(&self) -> u64 {
+      self.rdev
+   }
+
+   pub fn nlink(&self) -> u64 {
+      self.nlink
+   }
+
+   pub fn uid(&self) -> u32 {
+      self.uid
+   }
+
+   pub fn gid(&self) -> u32 {
+      self.gid
+   }
+
+   pub fn size(&self) -> i64 {
+      self.size
+   }
+
+   /// Czas ostatniego dostępu (czas lokalny).
+   pub fn atime(&self) -> datime::DateTime {
+      self.atime
+   }
+
+   /// Czas ostatniej modyfikacji (czas lokalny).
+   pub fn mtime(&self) -> datime::DateTime {
+      self.mtime
+   }
+
+   /// Czas ostatniej zmiany statusu (czas lokalny).
+   pub fn ctime(&self) -> datime::DateTime {
+      self.ctime
+   }
+
+   pub fn blksize(&self) -> i64 {
+      self.blksize
+   }
+
+   pub fn blocks(&self) -> i64 {
+      self.blocks
+   }
+}
+
+impl fmt::Display for Metadata {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      writeln!(f, "Information from stat {{")?;
+      writeln!(f, "\t   st_mode: {:?} {:o}", self.ftype, self.mode)?;
+      writeln!(f, "\t    st_ino: {}", self.inode)?;
+      writeln!(f, "\t    st_dev: {}", self.dev)?;
+      writeln!(f, "\t   st_rdev: {}", self.rdev)?;
+      writeln!(f, "\t  st_nlink: {}", self.nlink)?;
+      writeln!(f, "\t    st_uid: {}", self.uid)?;
+      writeln!(f, "\t    st_gid: {}", self.gid)?;
+      writeln!(f, "\t   st_size: {}", self.size)?;
+      writeln!(f, "\t  st_atime: {}", self.atime.as_string())?;
+      writeln!(f, "\t  st_mtime: {}", self.mtime.as_string())?;
+      writeln!(f, "\t  st_ctime: {}", self.ctime.as_string())?;
+      writeln!(f, "\tst_blksize: {}", self.blksize)?;
+      writeln!(f, "\t st_blocks: {}", self.blocks)?;
+      write!(f, "}}")
+   }
+}
+
+/// Czy ścieżka (po rozwinięciu linków) to katalog.
+fn is_dir(path: &str) -> bool {
+   matches!(stat(path), Ok(status) if stat2enum(status.st_mode) == FileType::Directory)
+}
+
+/// Tworzy katalog z przysłanymi prawami (przed nałożeniem umask).
+pub fn mkdir(path: &str, mode: u32) -> Result<(), FileError> {
+   let cstr = cpath("mkdir", path)?;
+   unsafe {
+      if libc::mkdir(cstr.as_ptr(), mode as libc::mode_t) == 0 {
+         return Ok(());
+      }
+   }
+   Err(FileError::last("mkdir", path))
+}
+
+/// Tworzy katalog razem z brakującymi katalogami nadrzędnymi.
+/// Istniejące katalogi nie są błędem.
+pub fn mkdir_all(path: &str, mode: u32) -> Result<(), FileError> {
+   let mut current = String::new();
+   if path.starts_with('/') {
+      current.push('/');
+   }
+   for part in path.split('/').filter(|part| !part.is_empty()) {
+      if !current.is_empty() && !current.ends_with('/') {
+         current.push('/');
+      }
+      current.push_str(part);
+      match mkdir(&current, mode) {
+         Err(err) if err.errno == libc::EEXIST && is_dir(&current) => (),
+         result => result?,
+      }
+   }
+   Ok(())
+}
+
+/// Usuwa plik lub katalog razem z całą zawartością.
+/// Linki symboliczne są usuwane, a nie to na co wskazują.
+pub fn remove_all(path: &str) -> Result<(), FileError> {
+   if stat2enum(lstat(path)?.st_mode) != FileType::Directory {
+      return remove(path);
+   }
+   let entries = read_dir(path)?.collect::<Result<Vec<_>, _>>()?;
+   for entry in entries {
+      remove_all(entry.path())?;
+   }
+   let cstr = cpath("rmdir", path)?;
+   unsafe {
+      if libc::rmdir(cstr.as_ptr()) == 0 {
+         return Ok(());
+      }
+   }
+   Err(FileError::last("rmdir", path))
+}
+
+/// Rodzaj blokady pliku.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LockKind {
+   /// Wielu czytelników naraz (LOCK_SH).
+   Shared,
+   /// Tylko jeden właściciel (LOCK_EX).
+   Exclusive,
+}
+
+/// Operacja 'flock' odpowiadająca rodzajowi blokady.
+fn lock_operation(kind: LockKind) -> i32 {
+   match kind {
+      LockKind::Shared => libc::LOCK_SH,
+      LockKind::Exclusive => libc::LOCK_EX,
+   }
+}
+
+/// Założona blokada pliku ('flock'), zwalniana przy usunięciu obiektu.
+/// Blokady są doradcze - chronią tylko przed procesami, które też ich używają.
+pub struct FileLock<'a> {
+   file: &'a File,
+   kind: LockKind,
+}
+
+impl<'a> FileLock<'a> {
+   pub fn kind(&self) -> LockKind {
+      self.kind
+   }
+}
+
+impl<'a> Drop for FileLock<'a> {
+   fn drop(&mut self) {
+      let _ = self.file.flock("unlock", libc::LOCK_UN);
+   }
+}
+
+/// Wykonuje funkcję trzymając blokadę wyłączną na pliku
+/// (tworzonym jeśli nie istnieje). Czeka aż blokada będzie wolna.
+pub fn with_lock<T, F: FnOnce(&File) -> T>(path: &str, f: F) -> Result<T, FileError> {
+   let file = OpenOptions::new().read(true).write(true).create(true).open(path)?;
+   let _lock = file.lock(LockKind::Exclusive)?;
+   Ok(f(&file))
+}
+
+/// Widok tylko do odczytu na całą zawartość pliku.
+/// Zwykły, niepusty plik jest mapowany do pamięci ('mmap'), więc nic
+/// nie jest kopiowane; dla pozostałych (potoki, /proc, urządzenia)
+/// zawartość jest wczytywana do bufora.
+/// Widok tworzą tylko funkcje 'unsafe' (map, File::map), bo zawartość
+/// mapowania nie jest chroniona przed zmianami pliku (patrz map).
+pub struct FileView {
+   data: ViewData,
+}
+
+enum ViewData {
+   Mapped(*mut libc::c_void, usize),
+   Owned(Vec<u8>),
+}
+
+/// Otwiera plik do odczytu i zwraca widok na jego zawartość.
+///
+/// # Safety
+///
+/// Plik nie może być modyfikowany ani skracany (przez ten ani inny
+/// proces) dopóki widok istnieje. Mimo MAP_PRIVATE zmiany zapisane do
+/// pliku są widoczne w niezmienionych stronach mapowania, więc bajty
+/// pod `&[u8]` mogłyby się zmienić (np. przestać być poprawnym UTF-8
+/// po walidacji), a skrócenie pliku kończy się sygnałem SIGBUS.
+pub unsafe fn map(path: &str) -> Result<FileView, FileError> {
+   OpenOptions::new().read(true).open(path)?.map()
+}
+
+impl FileView {
+   pub fn is_mapped(&self) -> bool {
+      matches!(self.data, ViewData::Mapped(..))
+   }
+}
+
+impl Deref for FileView {
+   type Target = [u8];
+
+   fn deref(&self) -> &[u8] {
+      match &self.data {
+         ViewData::Mapped(ptr, len) => unsafe { std::slice::from_raw_parts(*ptr as *const u8, *len) },
+         ViewData::Owned(buffer) => buffer,
+      }
+   }
+}
 
+impl Drop for FileView {
+   fn drop(&mut self) {
+      if let ViewData::Mapped(ptr, len) = self.data {
          unsafe {
-            if libc::rename(csrc.as_ptr(), cdst.as_ptr()) != -1 {
-               return true;
-            }
+            libc::munmap(ptr, len);
          }
-         perror(fpos!());
       }
    }
-   false
 }
 
-fn perror(info: &str) {
-   unsafe {
-      let cstr = CString::new(info).unwrap();      
-      libc::perror(cstr.as_ptr());
+/// Element katalogu.
+#[derive(Debug)]
+pub struct DirEntry {
+   name: String,
+   path: String,
+   ftype: FileType,
+}
+
+impl DirEntry {
+   pub fn name(&self) -> &str {
+      &self.name
+   }
+
+   pub fn path(&self) -> &str {
+      &self.path
+   }
+
+   /// Typ elementu; link symboliczny to zawsze Symlink.
+   pub fn ftype(&self) -> FileType {
+      self.ftype
+   }
+}
+
+/// Iterator po elementach katalogu (bez '.' i '..').
+/// Nazwy niebędące poprawnym UTF-8 są konwertowane ze stratą.
+/// Jeśli nie da się ustalić typu elementu, element zwracany jest
+/// z typem Unknown, a błąd - w następnym wywołaniu.
+pub struct ReadDir {
+   dir: *mut libc::DIR,
+   path: String,
+   pending_err: Option<FileError>,
+}
+
+/// Otwiera katalog do odczytu jego elementów.
+pub fn read_dir(path: &str) -> Result<ReadDir, FileError> {
+   let cstr = cpath("opendir", path)?;
+   let dir = unsafe { libc::opendir(cstr.as_ptr()) };
+   if dir.is_null() {
+      return Err(FileError::last("opendir", path));
    }
+   Ok(ReadDir { dir, path: String::from(path), pending_err: None })
 }
 
+impl Iterator for ReadDir {
+   type Item = Result<DirEntry, FileError>;
 
+   fn next(&mut self) -> Option<Self::Item> {
+      if let Some(err) = self.pending_err.take() {
+         return Some(Err(err));
+      }
+      loop {
+         let entry = unsafe {
+            set_errno(0);
+            libc::readdir(self.dir)
+         };
+         if entry.is_null() {
+            return match io::Error::last_os_error().raw_os_error() {
+               Some(0) | None => None,
+               Some(_) => Some(Err(FileError::last("readdir", &self.path))),
+            };
+         }
 
+         let (name, dtype) = unsafe {
+            let name = CStr::from_ptr((*entry).d_name.as_ptr()).to_string_lossy().into_owned();
+            (name, (*entry).d_type)
+         };
+         if name == "." || name == ".." {
+            continue;
+         }
 
-impl File {
+         let path = if self.path.ends_with('/') {
+            format!("{}{}", self.path, name)
+         } else {
+            format!("{}/{}", self.path, name)
+         };
+         let ftype = match dtype2enum(dtype) {
+            FileType::Unknown => match lstat(&path) {
+               Ok(status) => stat2enum(status.st_mode),
+               Err(err) => {
+                  self.pending_err = Some(err);
+                  FileType::Unknown
+               }
+            },
+            ftype => ftype,
+         };
+         return Some(Ok(DirEntry { name, path, ftype }));
+      }
+   }
+}
 
-   /// Otwiera plik do odczytu i zapisu.
-   pub fn open(&mut self) -> bool {
+impl Drop for ReadDir {
+   fn drop(&mut self) {
       unsafe {
-         if let Ok(cstr) = CString::new(&self.fpath[..]) {
-            let fd = libc::open(cstr.as_ptr(), libc::O_RDWR);
-            if fd != -1 {
-               self.fd = fd;
-               return true;
+         libc::closedir(self.dir);
+      }
+   }
+}
+
+#[cfg(target_os = "linux")]
+unsafe fn set_errno(value: i32) {
+   *libc::__errno_location() = value;
+}
+
+#[cfg(target_os = "macos")]
+unsafe fn set_errno(value: i32) {
+   *libc::__error() = value;
+}
+
+/// Przejście w głąb (depth-first) drzewa katalogów.
+/// Każdy element zwracany jest przed zawartością (jeśli to katalog).
+/// Przy 'follow_links' wchodzi też do katalogów wskazywanych przez linki;
+/// pomija tylko katalog (para st_dev, st_ino) będący jednym z aktualnie
+/// przechodzonych przodków, więc pętle z linków nie powodują nieskończonego
+/// przejścia, a katalog osiągalny kilkoma drogami zwracany jest dla każdej z nich.
+/// Katalog, do którego nie da się wejść, jest zwracany, a błąd - w następnym wywołaniu.
+pub struct Walk {
+   stack: Vec<(ReadDir, (libc::dev_t, libc::ino_t))>,
+   follow_links: bool,
+   pending_err: Option<FileError>,
+}
+
+pub fn walk(root: &str, follow_links: bool) -> Result<Walk, FileError> {
+   let status = stat(root)?;
+   Ok(Walk { stack: vec![(read_dir(root)?, (status.st_dev, status.st_ino))], follow_links, pending_err: None })
+}
+
+impl Walk {
+   /// Czy wejść do katalogu (lub linku do katalogu); zwraca jego identyfikator
+   /// jeśli tak, czyli gdy nie jest on żadnym z przodków na stosie.
+   fn should_descend(&self, entry: &DirEntry) -> Result<Option<(libc::dev_t, libc::ino_t)>, FileError> {
+      let status = match entry.ftype {
+         FileType::Directory => lstat(&entry.path)?,
+         FileType::Symlink if self.follow_links => match stat(&entry.path) {
+            Ok(status) => status,
+            // wiszący link - nie ma do czego wchodzić
+            Err(err) if err.errno == libc::ENOENT || err.errno == libc::ELOOP => return Ok(None),
+            Err(err) => return Err(err),
+         },
+         _ => return Ok(None),
+      };
+      if stat2enum(status.st_mode) != FileType::Directory {
+         return Ok(None);
+      }
+      let id = (status.st_dev, status.st_ino);
+      if self.stack.iter().any(|(_, ancestor)| *ancestor == id) {
+         return Ok(None);
+      }
+      Ok(Some(id))
+   }
+}
+
+impl Iterator for Walk {
+   type Item = Result<DirEntry, FileError>;
+
+   fn next(&mut self) -> Option<Self::Item> {
+      if let Some(err) = self.pending_err.take() {
+         return Some(Err(err));
+      }
+      loop {
+         let entry = match self.stack.last_mut()?.0.next() {
+            None => {
+               self.stack.pop();
+               continue;
             }
+            Some(Err(err)) => return Some(Err(err)),
+            Some(Ok(entry)) => entry,
+         };
+         match self.should_descend(&entry) {
+            Ok(Some(id)) => match read_dir(&entry.path) {
+               Ok(dir) => self.stack.push((dir, id)),
+               Err(err) => self.pending_err = Some(err),
+            },
+            Ok(None) => (),
+            Err(err) => self.pending_err = Some(err),
          }
+         return Some(Ok(entry));
       }
-      false
+   }
+}
+
+
+
+
+impl File {
+
+   /// Otwiera plik do odczytu i zapisu.
+   /// Jeśli ten obiekt ma już otwarty plik zwraca EBUSY.
+   pub fn open(&mut self) -> Result<(), FileError> {
+      self.open_with(OpenOptions::new().read(true).write(true))
    }
 
    /// Tworzy nowy plik.
-   /// Jeśli plik już istniał zwraca błąd.
-   pub fn create(&mut self) -> bool {
+   /// Jeśli plik już istniał zwraca błąd (EEXIST),
+   /// jeśli ten obiekt ma już otwarty plik - EBUSY.
+   pub fn create(&mut self) -> Result<(), FileError> {
+      self.open_fd("create", OpenOptions::new().read(true).write(true).create_new(true))
+   }
+
+   /// Otwiera plik zgodnie z przysłanymi opcjami.
+   pub fn open_with(&mut self, options: &OpenOptions) -> Result<(), FileError> {
+      self.open_fd("open", options)
+   }
+
+   fn open_fd(&mut self, op: &'static str, options: &OpenOptions) -> Result<(), FileError> {
+      if self.fd != -1 {
+         return Err(FileError::new(libc::EBUSY, op, &self.fpath));
+      }
+      let oflag = match options.flags() {
+         Some(oflag) => oflag,
+         None => return Err(FileError::new(libc::EINVAL, op, &self.fpath)),
+      };
+      let cstr = cpath(op, &self.fpath)?;
+      unsafe {
+         let fd = libc::open(cstr.as_ptr(), oflag, options.mode as libc::c_uint);
+         if fd != -1 {
+            self.fd = fd;
+            return Ok(());
+         }
+      }
+      Err(FileError::last(op, &self.fpath))
+   }
+
+   /// Zamyka plik. Zamknięcie już zamkniętego pliku nic nie robi.
+   /// Deskryptor jest zwalniany nawet gdy 'close' zwróci błąd
+   /// (tak robi Linux), więc nie próbujemy zamykać go drugi raz.
+   pub fn close(&mut self) -> Result<(), FileError> {
       if self.fd == -1 {
-         unsafe {
-            if let Ok(cstr) = CString::new(&self.fpath[..]) {
-               let oflag = libc::O_CREAT|libc::O_EXCL|libc::O_RDWR;
-               let omod = libc::S_IRUSR | libc::S_IWUSR | libc::S_IRGRP | libc::S_IWGRP | libc::S_IROTH;
-
-               let fd = libc::open(cstr.as_ptr(), oflag, omod);
-               if fd != -1 {
-                  self.fd = fd;
-                  return true;
-               }
-               perror(fpos!());
-               return false;
-            }
+         return Ok(());
+      }
+      let fd = self.fd;
+      self.fd = -1;
+      unsafe {
+         match libc::close(fd) {
+            0 => Ok(()),
+            _ => Err(FileError::last("close", &self.fpath)),
          }
       }
-      println!("the file already is open ({})", self.fpath);
-      false
    }
 
-   pub fn close(&mut self) -> bool {
+   /// Zapis buforów systemu na dysk ('fsync').
+   pub fn sync(&self) -> Result<(), FileError> {
       unsafe {
-         match libc::close(self.fd) {
-            0 => {
-               self.fd = -1;
-               true
-            }
-            _ => false,
+         if libc::fsync(self.fd) == 0 {
+            return Ok(());
          }
       }
+      Err(FileError::last("fsync", &self.fpath))
+   }
+
+   /// Zmiana praw dostępu otwartego pliku ('fchmod').
+   pub fn chmod(&self, mode: u32) -> Result<(), FileError> {
+      unsafe {
+         if libc::fchmod(self.fd, mode as libc::mode_t) == 0 {
+            return Ok(());
+         }
+      }
+      Err(FileError::last("chmod", &self.fpath))
+   }
+
+   /// Zakłada blokadę, czekając aż będzie to możliwe.
+   pub fn lock(&self, kind: LockKind) -> Result<FileLock<'_>, FileError> {
+      self.flock("lock", lock_operation(kind))?;
+      Ok(FileLock { file: self, kind })
+   }
+
+   /// Próbuje założyć blokadę bez czekania.
+   /// Zwraca None jeśli plik jest zablokowany przez kogoś innego.
+   pub fn try_lock(&self, kind: LockKind) -> Result<Option<FileLock<'_>>, FileError> {
+      match self.flock("lock", lock_operation(kind) | libc::LOCK_NB) {
+         Ok(()) => Ok(Some(FileLock { file: self, kind })),
+         Err(err) if err.errno == libc::EWOULDBLOCK => Ok(None),
+         Err(err) => Err(err),
+      }
+   }
+
+   /// Wywołanie 'flock' (ponawiane po EINTR).
+   fn flock(&self, op: &'static str, operation: i32) -> Result<(), FileError> {
+      loop {
+         if unsafe { libc::flock(self.fd, operation) } == 0 {
+            return Ok(());
+         }
+         let err = FileError::last(op, &self.fpath);
+         if err.errno != libc::EINTR {
+            return Err(err);
+         }
+      }
+   }
+
+   pub fn is_open(&self) -> bool {
+      self.fd != -1
+   }
+
+   pub fn path(&self) -> &str {
+      &self.fpath
    }
 
    /// Odczyt całej zawartości pliku.
-   pub fn read_all(&self) -> Option<Vec<u8>> {
-      if self.fd != -1 {
-         if let Some(nbytes) = self.size() {
-            if self.seek_begin() {
-               let nbytes = nbytes as usize;
-               let mut buffer: Vec<u8> = Vec::with_capacity(nbytes);
-               buffer.resize(nbytes, 0);
-
-               if self.read(&mut buffer) {
-                  buffer.shrink_to_fit();
-                  return Some(buffer);
-               }
-            }
+   /// Czyta do końca pliku (EOF), więc działa też dla potoków
+   /// i plików z /proc, dla których 'st_size' wynosi 0.
+   pub fn read_all(&self) -> Result<Vec<u8>, FileError> {
+      let hint = self.size()?.max(0) as usize;
+      match self.seek_begin() {
+         Err(err) if err.errno != libc::ESPIPE => return Err(err),
+         _ => (),
+      }
+
+      let mut buffer: Vec<u8> = vec![0; hint + READ_CHUNK];
+      let mut nbytes = 0;
+      loop {
+         if nbytes == buffer.len() {
+            buffer.resize(2 * nbytes, 0);
+         }
+         match self.read_some(&mut buffer[nbytes..])? {
+            0 => break,
+            n => nbytes += n,
          }
       }
-      None
+      buffer.truncate(nbytes);
+      buffer.shrink_to_fit();
+      Ok(buffer)
    }
 
    /// Odczyt określonej liczby bajtów z pliku.
-   /// Bajty odczytywane są do przesłanego wektora.
-   /// Liczbę bajtów do odczytu określa rozmiar tegoż wektora.
-   pub fn read(&self, buffer: &mut Vec<u8>) -> bool {
-      unsafe {
-         match libc::read(self.fd, buffer.as_mut_ptr() as *mut libc::c_void, buffer.len()) {
-            -1 => false,
-            _ => true
+   /// Bajty odczytywane są do przesłanego bufora.
+   /// Liczbę bajtów do odczytu określa rozmiar tegoż bufora.
+   /// Czyta aż do zapełnienia bufora lub końca pliku,
+   /// zwraca liczbę faktycznie odczytanych bajtów.
+   pub fn read_full(&self, buffer: &mut [u8]) -> Result<usize, FileError> {
+      let mut nbytes = 0;
+      while nbytes < buffer.len() {
+         match self.read_some(&mut buffer[nbytes..])? {
+            0 => break,
+            n => nbytes += n,
          }
       }
+      Ok(nbytes)
    }
 
+   /// Zapisuje do pliku wszystkie przysłane bajty.
+   /// Zwraca liczbę zapisanych bajtów (zawsze całość).
+   pub fn write_bytes(&self, bytes: &[u8]) -> Result<usize, FileError> {
+      let mut nbytes = 0;
+      while nbytes < bytes.len() {
+         match self.write_some(&bytes[nbytes..])? {
+            0 => return Err(FileError::new(libc::EIO, "write", &self.fpath)),
+            n => nbytes += n,
+         }
+      }
+      Ok(nbytes)
+   }
 
    /// Zapisuje przysłany tekst do pliku.
    /// Jeśli przysłana linia nie kończy się znakiem 'new line',
    /// to ten znak jest dodawany na jej końcu.
-   pub fn write_line(&self, text: &str) -> bool {
-      if self.fd != -1 {
-         let mut buffer: Vec<u8> = String::from(text).as_bytes().to_vec();
+   /// Zwraca liczbę zapisanych bajtów.
+   pub fn write_line(&self, text: &str) -> Result<usize, FileError> {
+      let mut buffer: Vec<u8> = String::from(text).as_bytes().to_vec();
 
-         if !buffer.is_empty() {
-            if buffer[buffer.len()-1] != b'\n' {
-               buffer.push(b'\n');
-            }
-            unsafe {
-               if libc::write(self.fd, buffer.as_ptr() as *const libc::c_void, buffer.len()) != 1 {
-                  return true;
-               }
-            }
+      if !buffer.is_empty() {
+         if buffer[buffer.len()-1] != b'\n' {
+            buffer.push(b'\n');
          }
+         return self.write_bytes(&buffer);
       }
-      false
+      Ok(0)
    }
 
-   pub fn size(&self) -> Option<i64> {
-      match self.stat() {
-         Some(st) => Some(st.st_size),
-         _ => None
+   /// Pojedyncze wywołanie 'read' (ponawiane po EINTR).
+   fn read_some(&self, buffer: &mut [u8]) -> Result<usize, FileError> {
+      loop {
+         let n = unsafe { libc::read(self.fd, buffer.as_mut_ptr() as *mut libc::c_void, buffer.len()) };
+         if n != -1 {
+            return Ok(n as usize);
+         }
+         let err = FileError::last("read", &self.fpath);
+         if err.errno != libc::EINTR {
+            return Err(err);
+         }
       }
    }
 
-   fn seek_current(&self) -> i64 {
-      unsafe {
-         let offset = libc::lseek(self.fd, 0, libc::SEEK_CUR.into());
-         offset
+   /// Pojedyncze wywołanie 'write' (ponawiane po EINTR).
+   fn write_some(&self, bytes: &[u8]) -> Result<usize, FileError> {
+      loop {
+         let n = unsafe { libc::write(self.fd, bytes.as_ptr() as *const libc::c_void, bytes.len()) };
+         if n != -1 {
+            return Ok(n as usize);
+         }
+         let err = FileError::last("write", &self.fpath);
+         if err.errno != libc::EINTR {
+            return Err(err);
+         }
       }
    }
 
-   fn seek_end(&self) -> Option<i64> {
-      unsafe {
-         let offset = libc::lseek(self.fd, 0, libc::SEEK_END.into());
-         if offset != -1 {
-            return Some(offset);
-         }
-      }
-      None
+   pub fn size(&self) -> Result<i64, FileError> {
+      self.stat().map(|st| st.st_size)
    }
 
-   fn seek_begin(&self) -> bool {
+   fn seek_current(&self) -> Result<i64, FileError> {
+      self.lseek(0, libc::SEEK_CUR)
+   }
+
+   fn seek_end(&self) -> Result<i64, FileError> {
+      self.lseek(0, libc::SEEK_END)
+   }
+
+   fn seek_begin(&self) -> Result<i64, FileError> {
+      self.lseek(0, libc::SEEK_SET)
+   }
+
+   fn lseek(&self, offset: i64, whence: i32) -> Result<i64, FileError> {
       unsafe {
-         let offset = libc::lseek(self.fd, 0, libc::SEEK_SET.into());
+         let offset = libc::lseek(self.fd, offset, whence);
          if offset != -1 {
-            return true;
+            return Ok(offset);
          }
       }
-      false
+      Err(FileError::last("seek", &self.fpath))
    }
 
-   /// Wydruk na konsoli informacji o pliku.
-   pub fn print_stat(&self) {
-      if let Some(st) = self.stat() {
-         println!();
-         println!("Information from stat {}", '{');
-         println!("\t   st_mode: {:?}", self.stat2enum(st.st_mode));
-         println!("\t    st_ino: {}", st.st_ino);
-         println!("\t    st_dev: {}", st.st_dev);
-         println!("\t   st_rdev: {}", st.st_rdev);
-         println!("\t  st_nlink: {}", st.st_nlink);
-         println!("\t    st_uid: {}", st.st_uid);
-         println!("\t    st_gid: {}", st.st_gid);
-         println!("\t   st_size: {}", st.st_size);
-         println!("\t  st_atime: {}", datime::local_from_tstamp(st.st_atime).unwrap().as_string());
-         println!("\t  st_mtime: {}", datime::local_from_tstamp(st.st_mtime).unwrap().as_string());
-         println!("\t  st_ctime: {}", datime::local_from_tstamp(st.st_ctime).unwrap().as_string());
-         println!("\tst_blksize: {}", st.st_blksize);
-         println!("\t st_blocks: {}", st.st_blocks);
-         println!("{}", '}');
+   /// Widok na całą zawartość otwartego pliku (patrz FileView).
+   /// Mapowanie pozostaje ważne także po zamknięciu pliku.
+   ///
+   /// # Safety
+   ///
+   /// Jak dla funkcji map: plik nie może być modyfikowany ani skracany
+   /// dopóki widok istnieje.
+   pub unsafe fn map(&self) -> Result<FileView, FileError> {
+      let status = self.stat()?;
+      if stat2enum(status.st_mode) != FileType::Regular || status.st_size <= 0 {
+         return Ok(FileView { data: ViewData::Owned(self.read_all()?) });
       }
+      let len = status.st_size as usize;
+      let ptr = libc::mmap(std::ptr::null_mut(), len, libc::PROT_READ, libc::MAP_PRIVATE, self.fd, 0);
+      if ptr == libc::MAP_FAILED {
+         return Err(FileError::last("mmap", &self.fpath));
+      }
+      Ok(FileView { data: ViewData::Mapped(ptr, len) })
+   }
+
+   /// Informacje o pliku.
+   /// Dla zamkniętego pliku będącego linkiem symbolicznym - o samym linku.
+   pub fn metadata(&self) -> Result<Metadata, FileError> {
+      Metadata::from_stat(&self.stat()?, &self.fpath)
    }
 
    /// Odczyt informacji o pliku.
    /// Jeśli plik jest otwarty używamy 'fstat' i deskryptora pliku,
-   /// w przeciwnym przypadku używamy 'stat' i ścieżkę do pliku.
-   fn stat(&self) -> Option<libc::stat> {
+   /// w przeciwnym przypadku używamy 'lstat' i ścieżkę do pliku
+   /// (więc link symboliczny nie jest rozwijany).
+   fn stat(&self) -> Result<libc::stat, FileError> {
+      if self.fd == -1 {
+         return lstat(&self.fpath);
+      }
       unsafe {
          let mut status: libc::stat = std::mem::zeroed();
-         match self.fd {
-            -1 => {
-               if let Ok(cstr) = CString::new(&self.fpath[..]) {
-                  if libc::stat(cstr.as_ptr(), &mut status) != -1 {
-                     return Some(status);
-                  }
-               }
-               None
-            }
-            _ => {
-               if libc::fstat(self.fd, &mut status) != -1 {
-                  return Some(status);
-               }
-               None
-            }
+         if libc::fstat(self.fd, &mut status) != -1 {
+            return Ok(status);
          }
       }
+      Err(FileError::last("fstat", &self.fpath))
    }
 
    /// Zwraca informację o typu pliku.
    /// Np. czy to file (Regular) czy Directory itd.
    pub fn ftype(&self) -> FileType {
       match self.stat() {
-         Some(status) => self.stat2enum(status.st_mode),
+         Ok(status) => stat2enum(status.st_mode),
          _ => FileType::Unknown,
       }
    }
 
-   fn stat2enum(&self, mode: u32) -> FileType {
-      match mode & libc::S_IFMT {
-         libc::S_IFREG  => FileType::Regular,
-         libc::S_IFDIR  => FileType::Directory,
-         libc::S_IFCHR  => FileType::CharacterSpecial,
-         libc::S_IFBLK  => FileType::BlockSpecial,
-         libc::S_IFIFO  => FileType::Fifo,
-         libc::S_IFLNK  => FileType::Symlink,
-         libc::S_IFSOCK => FileType::Socket,
-                      _ => FileType::Unknown
+}
+
+impl Drop for File {
+   fn drop(&mut self) {
+      let _ = self.close();
+   }
+}
+
+impl AsRawFd for File {
+   fn as_raw_fd(&self) -> RawFd {
+      self.fd
+   }
+}
+
+impl IntoRawFd for File {
+   /// Oddaje deskryptor wołającemu, obiekt go już nie zamknie.
+   fn into_raw_fd(mut self) -> RawFd {
+      let fd = self.fd;
+      self.fd = -1;
+      fd
+   }
+}
+
+impl FromRawFd for File {
+   /// Przejmuje na własność otwarty deskryptor (ścieżka pozostaje pusta).
+   unsafe fn from_raw_fd(fd: RawFd) -> File {
+      File {
+         fd,
+         fpath: String::new(),
       }
    }
+}
+
+impl io::Read for File {
+   fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+      Ok(self.read_some(buf)?)
+   }
+}
+
+impl io::Write for File {
+   fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+      Ok(self.write_some(buf)?)
+   }
+
+   fn flush(&mut self) -> io::Result<()> {
+      Ok(())
+   }
+}
+
+impl io::Seek for File {
+   fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+      let (offset, whence) = match pos {
+         io::SeekFrom::Start(n) => (n as i64, libc::SEEK_SET),
+         io::SeekFrom::End(n) => (n, libc::SEEK_END),
+         io::SeekFrom::Current(n) => (n, libc::SEEK_CUR),
+      };
+      Ok(self.lseek(offset, whence)? as u64)
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   fn temp_path(name: &str) -> String {
+      let dir = std::env::temp_dir();
+      format!("{}/file-rs-{}-{}", dir.display(), std::process::id(), name)
+   }
+
+   #[test]
+   fn test_error_details() {
+      let path = temp_path("missing");
+      let mut f = new(&path);
+      let err = f.open().unwrap_err();
+      assert_eq!(err.errno(), libc::ENOENT);
+      assert_eq!(err.op(), "open");
+      assert_eq!(err.path(), path);
+      assert_eq!(err.kind(), io::ErrorKind::NotFound);
+      assert!(!exists(&path).unwrap());
+      assert_eq!(remove(&path).unwrap_err().errno(), libc::ENOENT);
+      assert_eq!(cpath("open", "a\0b").unwrap_err().errno(), libc::EINVAL);
+   }
+
+   #[test]
+   fn test_create_write_read() {
+      let path = temp_path("create");
+      let _ = remove(&path);
+
+      let mut f = new(&path);
+      f.create().unwrap();
+      assert_eq!(f.create().unwrap_err().errno(), libc::EBUSY);
+      f.write_line("first").unwrap();
+      f.write_line("second\n").unwrap();
+      assert_eq!(f.read_all().unwrap(), b"first\nsecond\n");
+      f.close().unwrap();
+
+      assert_eq!(new(&path).create().unwrap_err().errno(), libc::EEXIST);
+      assert!(exists(&path).unwrap());
+
+      let moved = temp_path("create-moved");
+      rename(&path, &moved).unwrap();
+      remove(&moved).unwrap();
+   }
+
+   #[test]
+   fn test_ownership_and_io_traits() {
+      use std::io::{BufRead, Read, Seek, SeekFrom, Write};
+
+      let path = temp_path("traits");
+      let _ = remove(&path);
+
+      let mut f = new(&path);
+      f.create().unwrap();
+      assert_eq!(f.open().unwrap_err().errno(), libc::EBUSY);
+      f.write_all(b"alpha\nbeta\n").unwrap();
+      assert_eq!(f.seek(SeekFrom::Start(6)).unwrap(), 6);
+
+      let fd = f.into_raw_fd();
+      let mut f = unsafe { File::from_raw_fd(fd) };
+      assert_eq!(f.as_raw_fd(), fd);
+      let mut rest = String::new();
+      f.read_to_string(&mut rest).unwrap();
+      assert_eq!(rest, "beta\n");
+
+      f.seek(SeekFrom::Start(0)).unwrap();
+      let lines: Vec<String> = io::BufReader::new(&mut f).lines().map(|l| l.unwrap()).collect();
+      assert_eq!(lines, ["alpha", "beta"]);
+
+      f.close().unwrap();
+      f.close().unwrap();
+      assert!(!f.is_open());
+
+      let mut f = new(&path);
+      f.open().unwrap();
+      let mut copy: Vec<u8> = Vec::new();
+      io::copy(&mut f, &mut copy).unwrap();
+      assert_eq!(copy, b"alpha\nbeta\n");
+      drop(f);
+
+      remove(&path).unwrap();
+   }
+
+   #[test]
+   fn test_partial_read_and_pipes() {
+      let path = temp_path("partial");
+      let _ = remove(&path);
+
+      let mut f = new(&path);
+      f.create().unwrap();
+      assert_eq!(f.write_line("0123456789").unwrap(), 11);
+      assert_eq!(f.write_line("").unwrap(), 0);
+      f.seek_begin().unwrap();
+      let mut buffer = [0u8; 32];
+      assert_eq!(f.read_full(&mut buffer).unwrap(), 11);
+      assert_eq!(&buffer[..11], b"0123456789\n");
+      drop(f);
+      remove(&path).unwrap();
+
+      let mut fds = [0; 2];
+      assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+      let reader = unsafe { File::from_raw_fd(fds[0]) };
+      let writer = unsafe { File::from_raw_fd(fds[1]) };
+      let data = vec![b'x'; 3 * READ_CHUNK + 5];
+      let handle = std::thread::spawn(move || writer.write_bytes(&data).unwrap());
+      assert_eq!(reader.read_all().unwrap().len(), 3 * READ_CHUNK + 5);
+      assert_eq!(handle.join().unwrap(), 3 * READ_CHUNK + 5);
+
+      let fd = unsafe { libc::open(b"/proc/self/status\0".as_ptr() as *const libc::c_char, libc::O_RDONLY) };
+      assert_ne!(fd, -1);
+      let status = unsafe { File::from_raw_fd(fd) };
+      assert!(String::from_utf8(status.read_all().unwrap()).unwrap().contains("Pid:"));
+   }
+
+   #[test]
+   fn test_open_options() {
+      let path = temp_path("options");
+      let _ = remove(&path);
+
+      let f = OpenOptions::new().write(true).create_new(true).mode(0o600).open(&path).unwrap();
+      f.write_line("one").unwrap();
+      assert_eq!(f.stat().unwrap().st_mode & 0o777, 0o600);
+      drop(f);
+
+      let err = OpenOptions::new().write(true).create_new(true).open(&path).unwrap_err();
+      assert_eq!(err.errno(), libc::EEXIST);
+      assert_eq!(OpenOptions::new().open(&path).unwrap_err().errno(), libc::EINVAL);
+      assert_eq!(OpenOptions::new().read(true).truncate(true).open(&path).unwrap_err().errno(), libc::EINVAL);
+
+      let f = OpenOptions::new().append(true).open(&path).unwrap();
+      f.write_line("two").unwrap();
+      drop(f);
+
+      let f = OpenOptions::new().read(true).open(&path).unwrap();
+      assert_eq!(f.read_all().unwrap(), b"one\ntwo\n");
+      assert_eq!(f.write_line("three").unwrap_err().errno(), libc::EBADF);
+      drop(f);
+
+      let f = OpenOptions::new().write(true).truncate(true).cloexec(true).open(&path).unwrap();
+      assert_eq!(f.size().unwrap(), 0);
+      let fdflags = unsafe { libc::fcntl(f.as_raw_fd(), libc::F_GETFD) };
+      assert_ne!(fdflags & libc::FD_CLOEXEC, 0);
+      drop(f);
+
+      let link = temp_path("options-link");
+      let _ = remove(&link);
+      let (ctarget, clink) = (CString::new(&path[..]).unwrap(), CString::new(&link[..]).unwrap());
+      assert_eq!(unsafe { libc::symlink(ctarget.as_ptr(), clink.as_ptr()) }, 0);
+      let err = OpenOptions::new().read(true).nofollow(true).open(&link).unwrap_err();
+      assert_eq!(err.errno(), libc::ELOOP);
+      assert!(OpenOptions::new().read(true).open(&link).is_ok());
+
+      remove(&link).unwrap();
+      remove(&path).unwrap();
+   }
+
+   #[test]
+   fn test_whole_file_helpers() {
+      let path = temp_path("helpers");
+      let _ = remove(&path);
+
+      assert_eq!(read_file(&path).unwrap_err().errno(), libc::ENOENT);
+      assert_eq!(write_file(&path, b"one\r\ntwo").unwrap(), 8);
+      assert_eq!(append_file(&path, b"\nthree\n").unwrap(), 7);
+      assert_eq!(read_file(&path).unwrap(), b"one\r\ntwo\nthree\n");
+      assert_eq!(lines(&path).unwrap(), ["one", "two", "three"]);
+      assert_eq!(write_file(&path, b"new").unwrap(), 3);
+      assert_eq!(read_file(&path).unwrap(), b"new");
+
+      assert_eq!(file_type(&path).unwrap().to_string(), "file");
+      assert_eq!(file_type(&std::env::temp_dir().display().to_string()).unwrap().as_str(), "dir");
+      assert_eq!(file_type("/dev/null").unwrap().as_str(), "char_device");
+      remove(&path).unwrap();
+      assert_eq!(file_type(&path).unwrap_err().errno(), libc::ENOENT);
+   }
+
+   #[test]
+   fn test_directories() {
+      let root = temp_path("tree");
+      let _ = remove_all(&root);
+
+      mkdir_all(&format!("{}/a/b/", root), 0o755).unwrap();
+      mkdir_all(&format!("{}/a/b", root), 0o755).unwrap();
+      mkdir(&format!("{}/c", root), 0o700).unwrap();
+      assert_eq!(mkdir(&format!("{}/c", root), 0o700).unwrap_err().errno(), libc::EEXIST);
+      write_file(&format!("{}/a/b/f.txt", root), b"x").unwrap();
+      write_file(&format!("{}/c/g.txt", root), b"y").unwrap();
+      let file_as_dir = format!("{}/c/g.txt/h", root);
+      assert_eq!(mkdir_all(&file_as_dir, 0o755).unwrap_err().errno(), libc::EEXIST);
+
+      // pętla: a/b/up -> a
+      let (ctarget, clink) = (CString::new("..").unwrap(), CString::new(format!("{}/a/b/up", root)).unwrap());
+      assert_eq!(unsafe { libc::symlink(ctarget.as_ptr(), clink.as_ptr()) }, 0);
+
+      let mut names: Vec<(String, FileType)> = read_dir(&root)
+         .unwrap()
+         .map(|entry| entry.map(|e| (e.name().to_string(), e.ftype())).unwrap())
+         .collect();
+      names.sort_by(|x, y| x.0.cmp(&y.0));
+      assert_eq!(names, [("a".to_string(), FileType::Directory), ("c".to_string(), FileType::Directory)]);
+
+      let relative = |entry: DirEntry| entry.path()[root.len() + 1..].to_string();
+      let mut paths: Vec<String> = walk(&root, false).unwrap().map(|e| relative(e.unwrap())).collect();
+      paths.sort();
+      assert_eq!(paths, ["a", "a/b", "a/b/f.txt", "a/b/up", "c", "c/g.txt"]);
+
+      let mut paths: Vec<String> = walk(&root, true).unwrap().map(|e| relative(e.unwrap())).collect();
+      paths.sort();
+      assert_eq!(paths, ["a", "a/b", "a/b/f.txt", "a/b/up", "c", "c/g.txt"]);
+
+      // link bez pętli: c/alink -> ../a, katalog a osiągalny drugą drogą
+      let (ctarget, clink) = (CString::new("../a").unwrap(), CString::new(format!("{}/c/alink", root)).unwrap());
+      assert_eq!(unsafe { libc::symlink(ctarget.as_ptr(), clink.as_ptr()) }, 0);
+      let mut paths: Vec<String> = walk(&root, true).unwrap().map(|e| relative(e.unwrap())).collect();
+      paths.sort();
+      assert_eq!(
+         paths,
+         ["a", "a/b", "a/b/f.txt", "a/b/up", "c", "c/alink", "c/alink/b", "c/alink/b/f.txt", "c/alink/b/up", "c/g.txt"]
+      );
+
+      // remove_all usuwa link, a nie katalog na który wskazuje
+      remove_all(&format!("{}/a/b/up", root)).unwrap();
+      assert_eq!(file_type(&format!("{}/a", root)).unwrap(), FileType::Directory);
+
+      remove_all(&root).unwrap();
+      assert!(!exists(&root).unwrap());
+      assert_eq!(read_dir(&root).err().unwrap().errno(), libc::ENOENT);
+   }
+
+   #[cfg(target_os = "linux")]
+   #[test]
+   fn test_walk_unreadable_dir() {
+      let root = temp_path("walk-denied");
+      let _ = remove_all(&root);
+      mkdir(&root, 0o755).unwrap();
+      mkdir(&format!("{}/locked", root), 0o000).unwrap();
+      write_file(&format!("{}/open.txt", root), b"x").unwrap();
+
+      // root omija prawa dostępu - na czas przejścia ten wątek działa jako 'nobody'
+      let fsuid = unsafe { libc::setfsuid(65534) };
+      let items: Vec<Result<DirEntry, FileError>> = walk(&root, false).unwrap().collect();
+      unsafe { libc::setfsuid(fsuid as libc::uid_t) };
+
+      let locked = items.iter().position(|item| matches!(item, Ok(e) if e.name() == "locked")).unwrap();
+      assert_eq!(items[locked + 1].as_ref().unwrap_err().errno(), libc::EACCES);
+      let mut names: Vec<&str> = items.iter().filter_map(|item| item.as_ref().ok()).map(|e| e.name()).collect();
+      names.sort();
+      assert_eq!(names, ["locked", "open.txt"]);
+      assert_eq!(items.len(), 3);
+
+      remove_all(&root).unwrap();
+   }
+
+   #[test]
+   fn test_symlinks_and_metadata() {
+      let path = temp_path("meta");
+      let link = temp_path("meta-link");
+      let _ = remove(&path);
+      let _ = remove(&link);
+
+      write_file(&path, b"12345").unwrap();
+      symlink(&path, &link).unwrap();
+      assert_eq!(symlink(&path, &link).unwrap_err().errno(), libc::EEXIST);
+      assert_eq!(readlink(&link).unwrap(), path);
+      assert_eq!(readlink(&path).unwrap_err().errno(), libc::EINVAL);
+      assert_eq!(realpath(&link).unwrap(), realpath(&path).unwrap());
+      assert!(realpath(&link).unwrap().starts_with('/'));
+
+      assert_eq!(file_type(&link).unwrap(), FileType::Symlink);
+      assert_eq!(new(&link).ftype(), FileType::Symlink);
+      let meta = symlink_metadata(&link).unwrap();
+      assert_eq!(meta.ftype(), FileType::Symlink);
+      assert_eq!(meta.size(), path.len() as i64);
+
+      let meta = metadata(&link).unwrap();
+      assert_eq!(meta.ftype(), FileType::Regular);
+      assert_eq!(meta.size(), 5);
+      assert_eq!(meta.nlink(), 1);
+      assert_eq!(meta.uid(), unsafe { libc::getuid() });
+      assert_eq!(meta.inode(), symlink_metadata(&path).unwrap().inode());
+      assert!(meta.mtime().timestamp() > 0);
+      assert!(meta.to_string().contains("st_size: 5"));
+      assert_eq!(meta.rdev(), 0);
+
+      let null = metadata("/dev/null").unwrap();
+      assert_eq!(null.ftype(), FileType::CharacterSpecial);
+      assert_ne!(null.rdev(), 0);
+      assert!(null.to_string().contains(&format!("st_rdev: {}", null.rdev())));
+
+      let mut f = new(&link);
+      f.open().unwrap();
+      assert_eq!(f.metadata().unwrap().ftype(), FileType::Regular);
+      drop(f);
+
+      remove(&link).unwrap();
+      remove(&path).unwrap();
+   }
+
+   #[test]
+   fn test_map() {
+      let path = temp_path("map");
+      let _ = remove(&path);
+
+      write_file(&path, b"mapped content").unwrap();
+      // Pliki testowe nie są zmieniane dopóki widoki istnieją.
+      let f = OpenOptions::new().read(true).open(&path).unwrap();
+      let view = unsafe { f.map() }.unwrap();
+      drop(f);
+      assert!(view.is_mapped());
+      assert_eq!(&view[..], b"mapped content");
+
+      drop(view);
+      write_file(&path, b"").unwrap();
+      let view = unsafe { map(&path) }.unwrap();
+      assert!(!view.is_mapped());
+      assert!(view.is_empty());
+      remove(&path).unwrap();
+
+      let view = unsafe { map("/proc/self/status") }.unwrap();
+      assert!(!view.is_mapped());
+      assert!(view.starts_with(b"Name:"));
+      assert_eq!(unsafe { map(&path) }.err().unwrap().errno(), libc::ENOENT);
+   }
+
+   #[test]
+   fn test_write_atomic() {
+      let dir = temp_path("atomic");
+      let _ = remove_all(&dir);
+      mkdir(&dir, 0o755).unwrap();
+      let path = format!("{}/config", dir);
+      let link = format!("{}/config-link", dir);
 
+      write_atomic(&path, b"first").unwrap();
+      assert_eq!(read_file(&path).unwrap(), b"first");
+
+      let f = OpenOptions::new().write(true).open(&path).unwrap();
+      f.chmod(0o640).unwrap();
+      drop(f);
+      write_atomic(&path, b"second").unwrap();
+      assert_eq!(read_file(&path).unwrap(), b"second");
+      assert_eq!(metadata(&path).unwrap().mode(), 0o640);
+
+      symlink("config", &link).unwrap();
+      write_atomic(&link, b"third").unwrap();
+      assert_eq!(symlink_metadata(&link).unwrap().ftype(), FileType::Symlink);
+      assert_eq!(read_file(&path).unwrap(), b"third");
+
+      let names: Vec<String> = read_dir(&dir).unwrap().map(|e| e.unwrap().name().to_string()).collect();
+      assert_eq!(names.len(), 2);
+
+      // wiszący link ze ścieżką względną: pierwszy zapis tworzy plik
+      let dangling = format!("{}/new-link", dir);
+      mkdir(&format!("{}/sub", dir), 0o755).unwrap();
+      symlink("sub/new", &dangling).unwrap();
+      write_atomic(&dangling, b"fourth").unwrap();
+      assert_eq!(symlink_metadata(&dangling).unwrap().ftype(), FileType::Symlink);
+      assert_eq!(read_file(&format!("{}/sub/new", dir)).unwrap(), b"fourth");
+
+      let looped = format!("{}/loop", dir);
+      symlink("loop", &looped).unwrap();
+      assert_eq!(write_atomic(&looped, b"x").unwrap_err().errno(), libc::ELOOP);
+
+      let missing = format!("{}/no/such/dir/file", dir);
+      assert_eq!(write_atomic(&missing, b"x").unwrap_err().errno(), libc::ENOENT);
+      remove_all(&dir).unwrap();
+   }
+
+   #[test]
+   fn test_locks() {
+      let path = temp_path("lock");
+      let _ = remove(&path);
+      write_file(&path, b"").unwrap();
+
+      let first = OpenOptions::new().read(true).open(&path).unwrap();
+      let second = OpenOptions::new().read(true).open(&path).unwrap();
+
+      let shared = first.lock(LockKind::Shared).unwrap();
+      assert_eq!(shared.kind(), LockKind::Shared);
+      assert!(second.try_lock(LockKind::Shared).unwrap().is_some());
+      assert!(second.try_lock(LockKind::Exclusive).unwrap().is_none());
+      drop(shared);
+
+      let exclusive = second.try_lock(LockKind::Exclusive).unwrap().unwrap();
+      assert!(first.try_lock(LockKind::Shared).unwrap().is_none());
+      drop(exclusive);
+      assert!(first.try_lock(LockKind::Exclusive).unwrap().is_some());
+
+      let n = with_lock(&path, |file| {
+         assert!(second.try_lock(LockKind::Shared).unwrap().is_none());
+         file.write_bytes(b"state").unwrap()
+      });
+      assert_eq!(n.unwrap(), 5);
+      assert!(second.try_lock(LockKind::Exclusive).unwrap().is_some());
+      assert_eq!(read_file(&path).unwrap(), b"state");
+
+      let closed = new(&path);
+      assert_eq!(closed.try_lock(LockKind::Shared).err().unwrap().errno(), libc::EBADF);
+      remove(&path).unwrap();
+   }
 }
@@ -10,67 +10,138 @@ use std::fmt;
 
 pub type TokenType = &'static str;
 
-pub const ILLEGAL: &'static str = "ILLEGAL";
-pub const EOF: &'static str = "EOF";
-pub const IDENT: &'static str = "IDENT";
-pub const INT: &'static str = "INT";
+pub const ILLEGAL: TokenType = "ILLEGAL";
+pub const EOF: TokenType = "EOF";
+pub const IDENT: TokenType = "IDENT";
+pub const INT: TokenType = "INT";
+pub const STRING: TokenType = "STRING";
+pub const INTERP_STRING: TokenType = "INTERP_STRING";
 pub const ASSIGN: TokenType = "=";
-pub const PLUS: &'static str = "+";
-pub const MINUS: &'static str = "-";
-pub const BANG: &'static str = "!";
-pub const ASTRISK: &'static str = "*";
-pub const SLASH: &'static str = "/";
-pub const LT: &'static str = "<";
-pub const GT: &'static str = ">";
+pub const FAT_ARROW: TokenType = "=>";
+pub const PLUS_ASSIGN: TokenType = "+=";
+pub const MINUS_ASSIGN: TokenType = "-=";
+pub const ASTRISK_ASSIGN: TokenType = "*=";
+pub const SLASH_ASSIGN: TokenType = "/=";
+pub const PLUS: TokenType = "+";
+pub const MINUS: TokenType = "-";
+pub const BANG: TokenType = "!";
+pub const ASTRISK: TokenType = "*";
+pub const SLASH: TokenType = "/";
+pub const LT: TokenType = "<";
+pub const GT: TokenType = ">";
+pub const PIPE: TokenType = "|";
 pub const EQ: TokenType = "==";
-pub const NOT_EQ: &'static str = "==";
-pub const COMMA: &'static str = ",";
-pub const SEMICOLON: &'static str = ";";
-pub const LPAREN: &'static str = "(";
-pub const RPAREN: &'static str = ")";
-pub const LBRACE: &'static str = "{";
-pub const RBRACE: &'static str = "}";
-pub const FUNCTION: &'static str = "FUNCTION";
-pub const LET: &'static str = "LET";
-pub const TRUE: &'static str = "TRUE";
-pub const FALSE: &'static str = "FALSE";
-pub const IF: &'static str = "IF";
-pub const ELSE: &'static str = "ELSE";
-pub const RETURN: &'static str = "RETURN";
+pub const NOT_EQ: TokenType = "!=";
+pub const COMMA: TokenType = ",";
+pub const SEMICOLON: TokenType = ";";
+pub const COLON: TokenType = ":";
+pub const DOTDOT: TokenType = "..";
+pub const ELLIPSIS: TokenType = "...";
+pub const LPAREN: TokenType = "(";
+pub const RPAREN: TokenType = ")";
+pub const LBRACE: TokenType = "{";
+pub const RBRACE: TokenType = "}";
+pub const LBRACKET: TokenType = "[";
+pub const RBRACKET: TokenType = "]";
+pub const FUNCTION: TokenType = "FUNCTION";
+pub const LET: TokenType = "LET";
+pub const CONST: TokenType = "CONST";
+pub const TRUE: TokenType = "TRUE";
+pub const FALSE: TokenType = "FALSE";
+pub const IF: TokenType = "IF";
+pub const ELSE: TokenType = "ELSE";
+pub const RETURN: TokenType = "RETURN";
+pub const THROW: TokenType = "THROW";
+pub const TRY: TokenType = "TRY";
+pub const CATCH: TokenType = "CATCH";
+pub const FINALLY: TokenType = "FINALLY";
+pub const MACRO: TokenType = "MACRO";
+pub const MATCH: TokenType = "MATCH";
 
 lazy_static! {
     static ref KEYWORDS: HashMap<&'static str, &'static str> = {
         let mut keys = HashMap::new();
         keys.insert("fn", FUNCTION);
         keys.insert("let", LET);
+        keys.insert("const", CONST);
         keys.insert("true", TRUE);
         keys.insert("false", FALSE);
         keys.insert("if", IF);
         keys.insert("else", ELSE);
         keys.insert("return", RETURN);
+        keys.insert("throw", THROW);
+        keys.insert("try", TRY);
+        keys.insert("catch", CATCH);
+        keys.insert("finally", FINALLY);
+        keys.insert("macro", MACRO);
+        keys.insert("match", MATCH);
         keys
     };
 }
 
+/// Part of an interpolated string: plain text or the tokens of a `${...}` expression.
+#[derive(Debug, PartialEq)]
+pub enum Segment {
+    Text(String),
+    Code(Vec<Token>),
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Token {
     name: TokenType,
     literal: String,
+    line: usize,
+    column: usize,
+    segments: Vec<Segment>,
 }
 
 impl Token {
     pub fn new(name: TokenType, literal: String) -> Token {
-        Token { name, literal }
+        Token {
+            name,
+            literal,
+            line: 0,
+            column: 0,
+            segments: Vec::new(),
+        }
+    }
+
+    pub fn interpolated(literal: String, segments: Vec<Segment>) -> Token {
+        let mut token = Token::new(INTERP_STRING, literal);
+        token.segments = segments;
+        token
+    }
+
+    /// Sets the position (1-based line and column) of the first
+    /// character of the token in the source.
+    pub fn set_position(&mut self, line: usize, column: usize) {
+        self.line = line;
+        self.column = column;
     }
 
     #[inline]
     pub fn name(&self) -> TokenType {
-        return self.name;
+        self.name
     }
 
     #[inline]
     pub fn literal(&self) -> String {
-        return self.literal.clone();
+        self.literal.clone()
+    }
+
+    #[inline]
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    #[inline]
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    #[inline]
+    pub fn column(&self) -> usize {
+        self.column
     }
 
     #[inline]